@@ -0,0 +1,73 @@
+//! Benchmarks every solver in `SOLVERS` on generated inputs of increasing size,
+//! with the throughput in bytes per second. There is a benchmark group per day
+//! and part, like `day9_part_2`, with a line per variant in the report.
+//!
+//! Save the results of a commit with `cargo bench -- --save-baseline main` and
+//! compare a change against them with `cargo bench -- --baseline main`.
+//! Benchmarks can be filtered as usual, for example `cargo bench -- day1_`.
+
+use advent_of_code_2021::{generate, solver::SOLVERS};
+use criterion::{
+    black_box, criterion_group, criterion_main, AxisScale, BenchmarkId, Criterion,
+    PlotConfiguration, Throughput,
+};
+use itertools::Itertools;
+
+/// The seed of every generated input, so that runs compare the same inputs.
+const SEED: u64 = 2021;
+
+/// The sizes to generate the inputs of a day at, as passed to the generators.
+/// They stay within what the generators and solutions can handle, and what
+/// can be measured in reasonable time.
+fn sizes(day: u8) -> &'static [usize] {
+    match day {
+        1 | 2 | 6 | 7 => &[100, 1_000, 10_000, 100_000],
+        3 | 8 | 10 | 14 | 16 => &[10, 100, 1_000, 10_000],
+        5 | 13 => &[10, 100, 1_000],
+        4 | 17 | 18 | 20 | 25 => &[10, 30, 100],
+        9 | 15 => &[10, 30, 100, 300],
+        11 => &[5, 10],
+        12 => &[2, 4, 6, 8],
+        19 => &[2, 5, 10],
+        22 => &[10, 30, 100, 400],
+        24 => &[1, 3, 5, 7, 9],
+        // The size is not used
+        _ => &[1],
+    }
+}
+
+fn solvers(c: &mut Criterion) {
+    let groups = SOLVERS
+        .iter()
+        .group_by(|solver| (solver.day(), solver.part()));
+    for ((day, part), solvers) in &groups {
+        let solvers = solvers.collect_vec();
+        let mut group = c.benchmark_group(format!("day{}_part_{}", day, part));
+        group.plot_config(PlotConfiguration::default().summary_scale(AxisScale::Logarithmic));
+        for &size in sizes(day) {
+            let input = generate::generate(day, SEED, size).unwrap();
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            for solver in &solvers {
+                if let Err(e) = solver.solve(&input) {
+                    panic!(
+                        "day {} part {} ({}) failed on size {}: {:#}",
+                        day,
+                        part,
+                        solver.variant(),
+                        size,
+                        e
+                    );
+                }
+                group.bench_with_input(
+                    BenchmarkId::new(solver.variant(), size),
+                    input.as_str(),
+                    |b, input| b.iter(|| solver.solve(black_box(input))),
+                );
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, solvers);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+//! Computes the lookup tables of day 6 and day 21 ahead of time. These used to
+//! be constants evaluated by the compiler, which needed nightly features and
+//! made the build slow.
+
+use std::{env, fs, path::Path};
+
+#[path = "src/day21/tables.rs"]
+mod day21_tables;
+#[path = "src/day6/matrix.rs"]
+mod day6_matrix;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/day6/matrix.rs");
+    println!("cargo:rerun-if-changed=src/day21/tables.rs");
+
+    let out_dir = env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let out_dir = Path::new(&out_dir);
+
+    let day6 = format!(
+        "/// The number of fish a fish with a given timer becomes after 80 days.\n\
+         const MATRIX_80: [usize; BUFFER_LENGTH] = {:?};\n\
+         /// The number of fish a fish with a given timer becomes after 256 days.\n\
+         const MATRIX_256: [usize; BUFFER_LENGTH] = {:?};\n",
+        day6_matrix::fish_after(80),
+        day6_matrix::fish_after(256)
+    );
+    fs::write(out_dir.join("day6_tables.rs"), day6).expect("could not write the day 6 tables");
+
+    let day21 = format!(
+        "/// The answers to part 1 for every pair of starting positions.\n\
+         const FINAL_SCORE: [usize; 256] = {:?};\n\
+         /// The answers to part 2 for every pair of starting positions.\n\
+         const PART_2_SOLUTIONS: [usize; 256] = {:?};\n",
+        day21_tables::final_scores(),
+        day21_tables::part_2_solutions()
+    );
+    fs::write(out_dir.join("day21_tables.rs"), day21).expect("could not write the day 21 tables");
+}
@@ -0,0 +1,123 @@
+//! Checks every solution against the puzzle inputs in `input/2021`, using the
+//! answers listed in `input/2021/answers.txt`. Adding an input only takes
+//! adding the file and its answers there.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::solver::{Answer, SOLVERS};
+
+const INPUT_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/input/2021");
+const MANIFEST: &str = "answers.txt";
+
+/// Parse the manifest into the answers by input file and part. Every line
+/// holds the name of the file, the part and the answer, separated by a space.
+/// A `\n` in an answer stands for a newline. Empty lines and lines starting
+/// with `#` are skipped.
+fn parse_manifest(manifest: &str) -> HashMap<(&str, u8), Answer> {
+    let mut answers = HashMap::new();
+    for (i, line) in manifest.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(3, ' ');
+        let (file, part, answer) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(file), Some(part), Some(answer)) => (file, part, answer),
+            _ => panic!(
+                "{}:{}: expected a file, a part and an answer",
+                MANIFEST,
+                i + 1
+            ),
+        };
+        let part = part
+            .parse()
+            .unwrap_or_else(|_| panic!("{}:{}: invalid part {:?}", MANIFEST, i + 1, part));
+        let answer = answer
+            .parse()
+            .map(Answer::Number)
+            .unwrap_or_else(|_| Answer::Text(answer.replace("\\n", "\n")));
+        if answers.insert((file, part), answer).is_some() {
+            panic!(
+                "{}:{}: {} part {} is listed twice",
+                MANIFEST,
+                i + 1,
+                file,
+                part
+            );
+        }
+    }
+    answers
+}
+
+/// The day of an input file called `day<N>.txt` or `day<N>-<suffix>.txt`.
+fn day_of(file: &str) -> Option<u8> {
+    let name = file.strip_prefix("day")?.strip_suffix(".txt")?;
+    let day = name.split('-').next()?;
+    day.parse().ok()
+}
+
+#[test]
+fn test_parse_manifest() {
+    let answers = parse_manifest("# comment\nday1.txt 1 7\n\nday13.txt 2 #.\\n.#\\n");
+    assert_eq!(answers.len(), 2);
+    assert_eq!(answers[&("day1.txt", 1)], Answer::Number(7));
+    assert_eq!(answers[&("day13.txt", 2)], Answer::Text("#.\n.#\n".into()));
+}
+
+#[test]
+fn test_day_of() {
+    assert_eq!(day_of("day1.txt"), Some(1));
+    assert_eq!(day_of("day21-alice.txt"), Some(21));
+    assert_eq!(day_of("answers.txt"), None);
+    assert_eq!(day_of("day1.txt.orig"), None);
+}
+
+#[test]
+fn test_inputs() {
+    let directory = Path::new(INPUT_DIRECTORY);
+    let manifest = fs::read_to_string(directory.join(MANIFEST)).unwrap();
+    let answers = parse_manifest(&manifest);
+    let mut files = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    let mut checked = HashSet::new();
+    for file in files.iter() {
+        let day = match day_of(file) {
+            Some(day) => day,
+            None => continue,
+        };
+        let input = fs::read_to_string(directory.join(file)).unwrap();
+        for solver in SOLVERS.iter().filter(|solver| solver.day() == day) {
+            let key = (file.as_str(), solver.part());
+            let expected = answers.get(&key).unwrap_or_else(|| {
+                panic!("{} has no answer for {} part {}", MANIFEST, file, key.1)
+            });
+            let answer = solver.solve(&input).unwrap_or_else(|e| {
+                panic!("{} part {} ({}): {}", file, key.1, solver.variant(), e)
+            });
+            assert_eq!(
+                &answer,
+                expected,
+                "{} part {} ({})",
+                file,
+                key.1,
+                solver.variant()
+            );
+            checked.insert(key);
+        }
+    }
+    for (file, part) in answers.keys() {
+        assert!(
+            checked.contains(&(*file, *part)),
+            "{} lists {} part {}, but there is no such input or solution",
+            MANIFEST,
+            file,
+            part
+        );
+    }
+}
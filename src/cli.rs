@@ -0,0 +1,259 @@
+//! A command line mode that runs one part on any input file, for example
+//! `--day 7 --part 2 --variant mean --input crabs.txt`. The input is read from
+//! stdin when it is `-`. With `--json` the result is printed as a JSON object
+//! with the answer, the variant and the time it took in nanoseconds.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Read},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use thiserror::Error;
+
+use crate::solver::{self, Answer};
+
+pub const USAGE: &str = "usage: --day <N> --part <P> [--variant <name>] --input <path|-> [--json]";
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum CliError {
+    #[error("unknown argument {0:?}")]
+    UnknownArgument(String),
+    #[error("{0} expects a value")]
+    MissingValue(&'static str),
+    #[error("invalid value {1:?} for {0}")]
+    InvalidValue(&'static str, String),
+    #[error("{0} is required")]
+    MissingArgument(&'static str),
+    #[error("there is no solution for day {0} part {1}")]
+    NoSolution(u8, u8),
+    #[error("there is no variant {2:?} of day {0} part {1}")]
+    NoVariant(u8, u8, String),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Options {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<String>,
+    /// The path of the input file, or `-` for stdin.
+    pub input: String,
+    pub json: bool,
+}
+
+impl Options {
+    /// Parse the arguments, without the name of the program.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut args = args.into_iter();
+        let (mut day, mut part, mut variant, mut input) = (None, None, None, None);
+        let mut json = false;
+        while let Some(arg) = args.next() {
+            let flag = match arg.as_str() {
+                "--day" => "--day",
+                "--part" => "--part",
+                "--variant" => "--variant",
+                "--input" => "--input",
+                "--json" => {
+                    json = true;
+                    continue;
+                }
+                _ => return Err(CliError::UnknownArgument(arg)),
+            };
+            let value = args.next().ok_or(CliError::MissingValue(flag))?;
+            let number = |value: String| {
+                value
+                    .parse()
+                    .map_err(|_| CliError::InvalidValue(flag, value))
+            };
+            match flag {
+                "--day" => day = Some(number(value)?),
+                "--part" => part = Some(number(value)?),
+                "--variant" => variant = Some(value),
+                _ => input = Some(value),
+            }
+        }
+        Ok(Options {
+            day: day.ok_or(CliError::MissingArgument("--day"))?,
+            part: part.ok_or(CliError::MissingArgument("--part"))?,
+            variant,
+            input: input.ok_or(CliError::MissingArgument("--input"))?,
+            json,
+        })
+    }
+}
+
+/// The flags of the command line mode.
+const FLAGS: [&str; 5] = ["--day", "--part", "--variant", "--input", "--json"];
+
+/// Whether the arguments ask for the command line mode rather than for the
+/// runner of `aoc_main`. Any of its flags does, so that a missing `--day` is
+/// reported rather than ignored.
+pub fn is_requested(args: &[String]) -> bool {
+    args.iter().any(|arg| FLAGS.contains(&arg.as_str()))
+}
+
+fn read_input(path: &str) -> anyhow::Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        io::stdin()
+            .read_to_string(&mut input)
+            .context("could not read the input from stdin")?;
+        Ok(input)
+    } else {
+        fs::read_to_string(path).with_context(|| format!("could not read {}", path))
+    }
+}
+
+/// Write `s` as a JSON string.
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The result as a JSON object. Numbers are written as JSON numbers and text
+/// answers as strings.
+fn to_json(options: &Options, variant: &str, answer: &Answer, elapsed: Duration) -> String {
+    let mut json = format!(
+        "{{\"day\":{},\"part\":{},\"variant\":",
+        options.day, options.part
+    );
+    json_string(&mut json, variant);
+    json.push_str(",\"answer\":");
+    match answer {
+        Answer::Number(n) => write!(json, "{}", n).unwrap(),
+        Answer::Text(s) => json_string(&mut json, s),
+    }
+    write!(json, ",\"elapsed_ns\":{}}}", elapsed.as_nanos()).unwrap();
+    json
+}
+
+/// Run the part given by the arguments and print the answer.
+pub fn run(args: impl IntoIterator<Item = String>) -> anyhow::Result<()> {
+    let options = Options::parse(args)?;
+    let solver =
+        solver::find(options.day, options.part, options.variant.as_deref()).ok_or_else(|| {
+            match &options.variant {
+                Some(variant) if solver::find(options.day, options.part, None).is_some() => {
+                    CliError::NoVariant(options.day, options.part, variant.clone())
+                }
+                _ => CliError::NoSolution(options.day, options.part),
+            }
+        })?;
+    let input = read_input(&options.input)?;
+    let start = Instant::now();
+    let answer = solver.solve(&input)?;
+    let elapsed = start.elapsed();
+    if options.json {
+        println!("{}", to_json(&options, solver.variant(), &answer, elapsed));
+    } else {
+        println!("{}", answer);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn args(args: &str) -> Vec<String> {
+    args.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        Options::parse(args("--day 7 --part 2 --variant mean --input - --json")),
+        Ok(Options {
+            day: 7,
+            part: 2,
+            variant: Some("mean".into()),
+            input: "-".into(),
+            json: true,
+        })
+    );
+    assert_eq!(
+        Options::parse(args("--input in.txt --part 1 --day 25")),
+        Ok(Options {
+            day: 25,
+            part: 1,
+            variant: None,
+            input: "in.txt".into(),
+            json: false,
+        })
+    );
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        Options::parse(args("--day 7 --part")),
+        Err(CliError::MissingValue("--part"))
+    );
+    assert_eq!(
+        Options::parse(args("--day seven")),
+        Err(CliError::InvalidValue("--day", "seven".into()))
+    );
+    assert_eq!(
+        Options::parse(args("--day 7 --input -")),
+        Err(CliError::MissingArgument("--part"))
+    );
+    assert_eq!(
+        Options::parse(args("--day 7 -v")),
+        Err(CliError::UnknownArgument("-v".into()))
+    );
+}
+
+#[test]
+fn test_run_error() {
+    let error = |a| run(args(a)).unwrap_err().downcast::<CliError>().ok();
+    assert_eq!(
+        error("--day 25 --part 2 --input -"),
+        Some(CliError::NoSolution(25, 2))
+    );
+    assert_eq!(
+        error("--day 7 --part 2 --variant median --input -"),
+        Some(CliError::NoVariant(7, 2, "median".into()))
+    );
+    assert!(run(args("--day 1 --part 1 --input does/not/exist.txt")).is_err());
+}
+
+#[test]
+fn test_is_requested() {
+    assert!(is_requested(&args("--part 1 --day 1")));
+    assert!(is_requested(&args("--part 1 --input -")));
+    assert!(is_requested(&args("--json")));
+    assert!(!is_requested(&args("1 2 --bench")));
+}
+
+#[test]
+fn test_to_json() {
+    let options = Options::parse(args("--day 13 --part 2 --input -")).unwrap();
+    assert_eq!(
+        to_json(
+            &options,
+            "default",
+            &Answer::Text("#.\n\"\u{1}".into()),
+            Duration::from_micros(3)
+        ),
+        r##"{"day":13,"part":2,"variant":"default","answer":"#.\n\"\u0001","elapsed_ns":3000}"##
+    );
+    assert_eq!(
+        to_json(
+            &options,
+            "mean",
+            &Answer::Number(-12),
+            Duration::from_nanos(5)
+        ),
+        r#"{"day":13,"part":2,"variant":"mean","answer":-12,"elapsed_ns":5}"#
+    );
+}
@@ -1,36 +1,343 @@
+//! Day 1: Sonar Sweep. Counts how often the depth increases, for single
+//! measurements and for sliding windows of three. [`count_increases`] does the
+//! same for windows of any length. Depths may be negative or have decimals, but
+//! inputs of small unsigned numbers take a faster path, see [`Format`].
+//!
+//! ```
+//! use advent_of_code_2021::day1;
+//!
+//! let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+//! assert_eq!(day1::part_1(input), Ok(7));
+//! assert_eq!(day1::part_2(input), Ok(5));
+//! assert_eq!(day1::count_increases(input, 5), Ok(5));
+//! assert_eq!(day1::increase_positions(input, 5), Ok(vec![5, 6, 7, 8, 9]));
+//! ```
+
 use itertools::*;
+use thiserror::Error;
 
-pub fn part_1(input: &str) -> usize {
-    parse_numbers(input)
-        .tuple_windows()
-        .filter(|(a, b)| b > a)
-        .count()
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day1Error {
+    #[error("{0}: expected a digit")]
+    ExpectedDigit(Position),
+    #[error("{0}: a depth can have at most 8 digits")]
+    NumberTooLong(Position),
+    #[error("{0}: depth is too large")]
+    NumberTooLarge(Position),
+    #[error("{0}: a depth can have at most {1} decimals")]
+    TooManyDecimals(Position, u32),
+    #[error("the window should contain at least one measurement")]
+    EmptyWindow,
+}
+
+/// How the depths in an input are represented.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Unsigned numbers of at most 8 digits, which either all have the same
+    /// width or have no leading zeroes. They are parsed by [`parse`], which
+    /// packs the ASCII digits into a `u64`.
+    Packed,
+    /// Any other numbers, which may be negative or have a fractional part.
+    /// They are parsed by [`parse_fixed_point`] as integers in units of
+    /// `10^-decimals`, where `decimals` is the largest number of digits after
+    /// the decimal point.
+    FixedPoint { decimals: u32 },
+}
+
+/// Choose the format to parse the input with. The packed format is only used
+/// if packing the depths preserves their order.
+pub fn detect_format(input: &str) -> Format {
+    let input = normalize::line_endings(input);
+    detect(input.as_bytes())
 }
 
-pub fn part_2(input: &str) -> usize {
-    parse_numbers(input)
+fn detect(bytes: &[u8]) -> Format {
+    let (mut only_digits, mut uniform, mut leading_zero) = (true, true, false);
+    let mut width = None;
+    let mut decimals = 0;
+    for (_, line) in lines(bytes) {
+        only_digits &= line.len() <= 8 && line.iter().all(u8::is_ascii_digit);
+        uniform &= *width.get_or_insert(line.len()) == line.len();
+        leading_zero |= line.len() > 1 && line[0] == b'0';
+        if let Some(point) = memchr::memchr(b'.', line) {
+            decimals = decimals.max(line.len() - point - 1);
+        }
+    }
+    if only_digits && (uniform || !leading_zero) {
+        Format::Packed
+    } else {
+        Format::FixedPoint {
+            decimals: decimals as u32,
+        }
+    }
+}
+
+/// Count how often a depth is larger than the one before it.
+fn increases_in_pairs<T: Ord + Clone>(depths: impl Iterator<Item = T>) -> usize {
+    depths.tuple_windows().filter(|(a, b)| b > a).count()
+}
+
+/// Count how often a sliding window of three depths has a larger sum than the
+/// window before it.
+fn increases_in_windows_of_three<T: Ord + Clone>(depths: impl Iterator<Item = T>) -> usize {
+    depths
         .tuple_windows()
         // Compare the value that is removed with the one that is added
         .filter(|(removed, _, _, added)| added > removed)
         .count()
 }
 
-fn parse_numbers(input: &str) -> impl Iterator<Item = u64> + '_ {
-    let mut iter = input
-        .as_bytes()
-        .iter()
-        .copied()
-        .inspect(|&a| debug_assert!(a.is_ascii_digit() || a == b'\n'));
-    std::iter::from_fn(move || {
-        iter.next().map(|x| {
-            (&mut iter)
-                .take_while(|b| *b != b'\n')
-                // Map to u64, preserve order
-                .fold(u64::from(x), |acc, digit| (acc << 8) | u64::from(digit))
+pub fn part_1(input: &str) -> Result<usize, Day1Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    // Most inputs are in the packed format, which is checked along the way
+    let mut packed = true;
+    let count = increases_in_pairs(packed_depths(bytes).map_while(|depth| {
+        packed &= depth.is_some();
+        depth
+    }));
+    if packed {
+        return Ok(count);
+    }
+    match detect(bytes) {
+        Format::Packed => process_results(depths(bytes), |depths| increases_in_pairs(depths)),
+        Format::FixedPoint { decimals } => {
+            process_results(fixed_point_depths(bytes, decimals), |depths| {
+                increases_in_pairs(depths)
+            })
+        }
+    }
+}
+
+pub fn part_2(input: &str) -> Result<usize, Day1Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    // Most inputs are in the packed format, which is checked along the way
+    let mut packed = true;
+    let count = increases_in_windows_of_three(packed_depths(bytes).map_while(|depth| {
+        packed &= depth.is_some();
+        depth
+    }));
+    if packed {
+        return Ok(count);
+    }
+    match detect(bytes) {
+        Format::Packed => process_results(depths(bytes), |depths| {
+            increases_in_windows_of_three(depths)
+        }),
+        Format::FixedPoint { decimals } => {
+            process_results(fixed_point_depths(bytes, decimals), |depths| {
+                increases_in_windows_of_three(depths)
+            })
+        }
+    }
+}
+
+pub fn part_1_ring_buffer(input: &str) -> Result<usize, Day1Error> {
+    count_increases(input, 1)
+}
+
+pub fn part_2_ring_buffer(input: &str) -> Result<usize, Day1Error> {
+    count_increases(input, 3)
+}
+
+/// Count how often the sum of a sliding window of `window` measurements is
+/// larger than the sum of the window before it.
+pub fn count_increases(input: &str, window: usize) -> Result<usize, Day1Error> {
+    fn count(
+        mut increases: impl Iterator<Item = Result<Option<usize>, Day1Error>>,
+    ) -> Result<usize, Day1Error> {
+        increases.try_fold(0, |count, increase| {
+            increase.map(|increase| count + usize::from(increase.is_some()))
         })
+    }
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    match detect(bytes) {
+        Format::Packed => count(increases(depths(bytes), window)?),
+        Format::FixedPoint { decimals } => {
+            count(increases(fixed_point_depths(bytes, decimals), window)?)
+        }
+    }
+}
+
+/// The indices of the measurements that end a window with a larger sum than
+/// the window before it. Measurement `i` is on line `i + 1` of the input.
+pub fn increase_positions(input: &str, window: usize) -> Result<Vec<usize>, Day1Error> {
+    fn positions(
+        increases: impl Iterator<Item = Result<Option<usize>, Day1Error>>,
+    ) -> Result<Vec<usize>, Day1Error> {
+        increases.filter_map(Result::transpose).collect()
+    }
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    match detect(bytes) {
+        Format::Packed => positions(increases(depths(bytes), window)?),
+        Format::FixedPoint { decimals } => {
+            positions(increases(fixed_point_depths(bytes, decimals), window)?)
+        }
+    }
+}
+
+/// Yields for every measurement its index if it ends a window with a larger
+/// sum than the window before it, and `None` otherwise. Two consecutive
+/// windows only differ in the measurement that is removed and the one that is
+/// added, so only the last `window` measurements are kept, in a ring buffer.
+fn increases<T: Ord + Copy>(
+    depths: impl Iterator<Item = Result<T, Day1Error>>,
+    window: usize,
+) -> Result<impl Iterator<Item = Result<Option<usize>, Day1Error>>, Day1Error> {
+    if window == 0 {
+        return Err(Day1Error::EmptyWindow);
+    }
+    let mut ring_buffer = Vec::with_capacity(window);
+    Ok(depths.enumerate().map(move |(i, depth)| {
+        let added = depth?;
+        if ring_buffer.len() < window {
+            ring_buffer.push(added);
+            return Ok(None);
+        }
+        let removed = std::mem::replace(&mut ring_buffer[i % window], added);
+        Ok((added > removed).then_some(i))
+    }))
+}
+
+/// Yields the lines of the input, with their offsets.
+fn lines(bytes: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset >= bytes.len() {
+            return None;
+        }
+        let line_end =
+            memchr::memchr(b'\n', &bytes[offset..]).map_or(bytes.len(), |pos| offset + pos);
+        let line = (offset, &bytes[offset..line_end]);
+        offset = line_end + 1;
+        Some(line)
+    })
+}
+
+/// Yields the depths in the input. They are not converted to their actual
+/// values, but the ASCII digits are packed into a `u64` instead, which
+/// preserves their order in the packed format.
+fn depths(bytes: &[u8]) -> impl Iterator<Item = Result<u64, Day1Error>> + '_ {
+    lines(bytes).map(move |(offset, line)| packed_depth(bytes, offset, line))
+}
+
+/// Like [`depths`], but yields `None` instead as soon as a line shows that the
+/// input is not in the packed format, see [`detect`].
+fn packed_depths(bytes: &[u8]) -> impl Iterator<Item = Option<u64>> + '_ {
+    let (mut width, mut uniform, mut leading_zero) = (None, true, false);
+    lines(bytes).map(move |(offset, line)| {
+        uniform &= *width.get_or_insert(line.len()) == line.len();
+        leading_zero |= line.len() > 1 && line[0] == b'0';
+        if uniform || !leading_zero {
+            packed_depth(bytes, offset, line).ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// The depth on the line starting at `offset`, with its ASCII digits packed
+/// into a `u64`.
+fn packed_depth(bytes: &[u8], offset: usize, line: &[u8]) -> Result<u64, Day1Error> {
+    if line.is_empty() {
+        return Err(Day1Error::ExpectedDigit(Position::of(bytes, offset)));
+    }
+    if line.len() > 8 {
+        return Err(Day1Error::NumberTooLong(Position::of(bytes, offset)));
+    }
+    let mut number = 0;
+    for (i, digit) in line.iter().enumerate() {
+        if !digit.is_ascii_digit() {
+            return Err(Day1Error::ExpectedDigit(Position::of(bytes, offset + i)));
+        }
+        // Map to u64, preserve order
+        number = (number << 8) | u64::from(*digit);
+    }
+    Ok(number)
+}
+
+/// Yields the depths in the input as integers in units of `10^-decimals`.
+fn fixed_point_depths(
+    bytes: &[u8],
+    decimals: u32,
+) -> impl Iterator<Item = Result<i64, Day1Error>> + '_ {
+    lines(bytes).map(move |(offset, line)| {
+        let error_at = |i| Position::of(bytes, offset + i);
+        let sign_length = usize::from(line.first() == Some(&b'-'));
+        let mut number: i64 = 0;
+        let mut fraction_digits = None;
+        for (i, &b) in line.iter().enumerate().skip(sign_length) {
+            match (b, &mut fraction_digits) {
+                (b'.', None) if i > sign_length => fraction_digits = Some(0),
+                (b'0'..=b'9', _) => {
+                    number = number
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(i64::from(b - b'0')))
+                        .ok_or_else(|| Day1Error::NumberTooLarge(error_at(0)))?;
+                    if let Some(fraction_digits) = &mut fraction_digits {
+                        *fraction_digits += 1;
+                    }
+                }
+                _ => return Err(Day1Error::ExpectedDigit(error_at(i))),
+            }
+        }
+        let fraction_digits = match fraction_digits {
+            // There should be a digit after the sign or the decimal point
+            _ if line.len() == sign_length || line.last() == Some(&b'.') => {
+                return Err(Day1Error::ExpectedDigit(error_at(line.len())))
+            }
+            Some(fraction_digits) if fraction_digits > decimals => {
+                return Err(Day1Error::TooManyDecimals(error_at(0), decimals))
+            }
+            fraction_digits => fraction_digits.unwrap_or(0),
+        };
+        let number = 10i64
+            .checked_pow(decimals - fraction_digits)
+            .and_then(|scale| number.checked_mul(scale))
+            .ok_or_else(|| Day1Error::NumberTooLarge(error_at(0)))?;
+        Ok(if sign_length == 1 { -number } else { number })
     })
 }
 
+/// Parse the depths in the packed format, see [`Format::Packed`]. The ASCII
+/// digits are packed into a `u64`, which preserves the order of the depths.
+pub fn parse(input: &str) -> Result<Vec<u64>, Day1Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let mut numbers = Vec::with_capacity(bytes.len() / 4);
+    for depth in depths(bytes) {
+        numbers.push(depth?);
+    }
+    Ok(numbers)
+}
+
+/// Parse the depths in the fixed point format, see [`Format::FixedPoint`].
+pub fn parse_fixed_point(input: &str, decimals: u32) -> Result<Vec<i64>, Day1Error> {
+    let input = normalize::line_endings(input);
+    fixed_point_depths(input.as_bytes(), decimals).collect()
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("199\n2x0"),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse("199\n\n200"),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse("123456789"),
+        Err(Day1Error::NumberTooLong(Position { line: 1, column: 1 }))
+    );
+}
+
 #[test]
 fn test_part_1_example() {
     let input = r#"199
@@ -43,13 +350,7 @@ fn test_part_1_example() {
 269
 260
 263"#;
-    assert_eq!(part_1(input), 7);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day1.txt");
-    assert_eq!(part_1(input), 1791);
+    assert_eq!(part_1(input), Ok(7));
 }
 
 #[test]
@@ -64,11 +365,112 @@ fn test_part_2_example() {
 269
 260
 263"#;
-    assert_eq!(part_2(input), 5);
+    assert_eq!(part_2(input), Ok(5));
+}
+
+#[test]
+fn test_count_increases() {
+    let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+    assert_eq!(count_increases(input, 1), part_1(input));
+    assert_eq!(count_increases(input, 3), part_2(input));
+    assert_eq!(count_increases(input, 10), Ok(0));
+    assert_eq!(count_increases(input, 11), Ok(0));
+    assert_eq!(count_increases("", 2), Ok(0));
+    assert_eq!(count_increases(input, 0), Err(Day1Error::EmptyWindow));
+    assert_eq!(
+        count_increases("1\n2\nx", 4),
+        Err(Day1Error::ExpectedDigit(Position { line: 3, column: 1 }))
+    );
+}
+
+#[test]
+fn test_increase_positions() {
+    let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+    assert_eq!(increase_positions(input, 1), Ok(vec![1, 2, 3, 5, 6, 7, 9]));
+    assert_eq!(increase_positions(input, 3), Ok(vec![3, 6, 7, 8, 9]));
+    assert_eq!(increase_positions("3\n1\n1", 2), Ok(vec![]));
+}
+
+#[test]
+fn test_detect_format() {
+    assert_eq!(detect_format("199\n200\n1"), Format::Packed);
+    assert_eq!(detect_format("099\n100"), Format::Packed);
+    assert_eq!(detect_format("0\n10"), Format::Packed);
+    assert_eq!(
+        detect_format("099\n1000"),
+        Format::FixedPoint { decimals: 0 }
+    );
+    assert_eq!(
+        detect_format("123456789"),
+        Format::FixedPoint { decimals: 0 }
+    );
+    assert_eq!(
+        detect_format("-1\n2.5\n3.125"),
+        Format::FixedPoint { decimals: 3 }
+    );
+}
+
+#[test]
+fn test_parse_fixed_point() {
+    assert_eq!(
+        parse_fixed_point("-1\n2.5\n3.125\n0099", 3),
+        Ok(vec![-1000, 2500, 3125, 99000])
+    );
+    assert_eq!(parse_fixed_point("123456789", 0), Ok(vec![123456789]));
+    assert_eq!(
+        parse_fixed_point("1\n2.25", 1),
+        Err(Day1Error::TooManyDecimals(
+            Position { line: 2, column: 1 },
+            1
+        ))
+    );
+    assert_eq!(
+        parse_fixed_point("1\n-", 0),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse_fixed_point("1.\n2", 0),
+        Err(Day1Error::ExpectedDigit(Position { line: 1, column: 3 }))
+    );
+    assert_eq!(
+        parse_fixed_point("1\n.5", 1),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse_fixed_point("1\n2-3", 0),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse_fixed_point("1\n\n2", 0),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse_fixed_point("99999999999999999999", 0),
+        Err(Day1Error::NumberTooLarge(Position { line: 1, column: 1 }))
+    );
+    assert_eq!(
+        parse_fixed_point("99999999999", 9),
+        Err(Day1Error::NumberTooLarge(Position { line: 1, column: 1 }))
+    );
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day1.txt");
-    assert_eq!(part_2(input), 1822);
+fn test_mixed_formats() {
+    let input = "9\n10\n-1.5\n-1.25\n0099\n100";
+    assert_eq!(part_1(input), Ok(4));
+    assert_eq!(part_2(input), Ok(2));
+    assert_eq!(part_1_ring_buffer(input), Ok(4));
+    assert_eq!(part_2_ring_buffer(input), Ok(2));
+    assert_eq!(increase_positions(input, 1), Ok(vec![1, 3, 4, 5]));
+    assert_eq!(
+        part_1("1\n-x"),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    // Only the last line shows that packing would not preserve the order
+    assert_eq!(part_1("9\n10\n05"), Ok(1));
+    assert_eq!(part_2("9\n10\n11\n05"), Ok(0));
+    assert_eq!(
+        part_2("199\n\n200"),
+        Err(Day1Error::ExpectedDigit(Position { line: 2, column: 1 }))
+    );
 }
@@ -1,9 +1,34 @@
+//! Day 10: Syntax Scoring. Scores the corrupted and the incomplete lines of
+//! chunks.
+//!
+//! ```
+//! use advent_of_code_2021::day10;
+//!
+//! assert_eq!(day10::part_1("{([(<{}[<>[]}>{[]{[(<()>"), Ok(1197));
+//! assert_eq!(day10::part_2("[({(<(())[]>[[{[]{<()<>>"), Ok(288957));
+//! ```
+
 use std::fmt;
 
 use arrayvec::ArrayVec;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
 
 const STACK_SIZE: usize = 120;
 
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day10Error {
+    #[error("{0}: expected a bracket")]
+    InvalidCharacter(Position),
+    #[error("{0}: closing bracket without an opening bracket")]
+    UnexpectedCloser(Position),
+    #[error("{0}: chunks are nested more than {} deep", STACK_SIZE)]
+    TooDeeplyNested(Position),
+    #[error("there are no incomplete lines")]
+    NoIncompleteLines,
+}
+
 #[derive(Eq, PartialEq)]
 enum ParseResult<'a> {
     Valid,
@@ -79,45 +104,76 @@ fn matches(a: u8, b: u8) -> bool {
     (a ^ b) < 0b111
 }
 
-fn parse<'a>(stack: &'a mut ArrayVec<u8, STACK_SIZE>, input: &mut &[u8]) -> ParseResult<'a> {
+/// Parse the line at the start of `remainder`, which is a suffix of `input`.
+fn parse_line<'a>(
+    stack: &'a mut ArrayVec<u8, STACK_SIZE>,
+    input: &[u8],
+    remainder: &mut &[u8],
+) -> Result<ParseResult<'a>, Day10Error> {
     debug_assert!(stack.is_empty());
     loop {
-        let first = if let Some((first, remainder)) = input.split_first() {
-            *input = remainder;
+        let first = if let Some((first, rest)) = remainder.split_first() {
+            *remainder = rest;
             *first
         } else {
             b'\n'
         };
+        let position = || Position::of(input, input.len() - remainder.len() - 1);
         match first {
             b'\n' => {
-                return if stack.is_empty() {
+                return Ok(if stack.is_empty() {
                     ParseResult::Valid
                 } else {
                     ParseResult::Incomplete { stack }
-                }
+                })
             }
-            c if is_opening(c) => stack.push(c),
-            found => {
+            b'(' | b'[' | b'{' | b'<' => stack
+                .try_push(first)
+                .map_err(|_| Day10Error::TooDeeplyNested(position()))?,
+            b')' | b']' | b'}' | b'>' => {
                 // Corrupted means either not matching or no character on the stack
-                let expected = stack.pop().unwrap();
-                if !matches(expected, found) {
+                let expected = stack
+                    .pop()
+                    .ok_or_else(|| Day10Error::UnexpectedCloser(position()))?;
+                if !matches(expected, first) {
                     // Flush remainder of line
-                    *input = memchr::memchr(b'\n', input)
-                        .map(|pos| &input[pos..])
+                    *remainder = memchr::memchr(b'\n', remainder)
+                        .map(|pos| &remainder[pos..])
                         .unwrap_or(&[]);
-                    return ParseResult::Corrupted { expected, found };
+                    return Ok(ParseResult::Corrupted {
+                        expected,
+                        found: first,
+                    });
                 }
             }
+            _ => return Err(Day10Error::InvalidCharacter(position())),
         }
     }
 }
 
-pub fn part_1(input: &str) -> usize {
+/// Check that every line consists of brackets, and split the input into lines.
+pub fn parse(input: &str) -> Result<Vec<&str>, Day10Error> {
+    let mut bytes = input.as_bytes();
+    let mut stack = ArrayVec::new();
+    let mut lines = Vec::new();
+    while !bytes.is_empty() {
+        let start = input.len() - bytes.len();
+        parse_line(&mut stack, input.as_bytes(), &mut bytes)?;
+        lines.push(input[start..input.len() - bytes.len()].trim_end_matches('\n'));
+        stack.clear();
+    }
+    Ok(lines)
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day10Error> {
+    let input = normalize::line_endings(input);
     let mut bytes = input.as_bytes();
     let mut stack = ArrayVec::new();
     let mut acc = 0;
     while !bytes.is_empty() {
-        if let ParseResult::Corrupted { found, .. } = parse(&mut stack, &mut bytes) {
+        if let ParseResult::Corrupted { found, .. } =
+            parse_line(&mut stack, input.as_bytes(), &mut bytes)?
+        {
             acc += match found {
                 b')' => 3,
                 b']' => 57,
@@ -130,15 +186,18 @@ pub fn part_1(input: &str) -> usize {
         }
         stack.clear();
     }
-    acc
+    Ok(acc)
 }
 
-pub fn part_2(input: &str) -> usize {
+pub fn part_2(input: &str) -> Result<usize, Day10Error> {
+    let input = normalize::line_endings(input);
     let mut bytes = input.as_bytes();
     let mut stack = ArrayVec::new();
-    let mut acc = ArrayVec::<usize, STACK_SIZE>::new();
+    let mut acc = Vec::new();
     while !bytes.is_empty() {
-        if let ParseResult::Incomplete { stack } = parse(&mut stack, &mut bytes) {
+        if let ParseResult::Incomplete { stack } =
+            parse_line(&mut stack, input.as_bytes(), &mut bytes)?
+        {
             acc.push(
                 stack
                     .drain(..)
@@ -157,8 +216,11 @@ pub fn part_2(input: &str) -> usize {
         }
         stack.clear();
     }
+    if acc.is_empty() {
+        return Err(Day10Error::NoIncompleteLines);
+    }
     let l = acc.len();
-    *acc.select_nth_unstable(l / 2).1
+    Ok(*acc.select_nth_unstable(l / 2).1)
 }
 
 #[test]
@@ -173,7 +235,7 @@ fn test_part_1_example() {
 [<(<(<(<{}))><([]([]()
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]";
-    assert_eq!(part_1(input), 26397);
+    assert_eq!(part_1(input), Ok(26397));
 }
 
 #[test]
@@ -188,19 +250,34 @@ fn test_part_2_example() {
 [<(<(<(<{}))><([]([]()
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]";
-    assert_eq!(part_2(input), 288957);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day10.txt");
-    assert_eq!(part_1(input), 323613);
+    assert_eq!(part_2(input), Ok(288957));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day10.txt");
-    assert_eq!(part_2(input), 3103006161);
+fn test_parse_error() {
+    assert_eq!(parse("[<>]\n(()"), Ok(vec!["[<>]", "(()"]));
+    assert_eq!(
+        parse("[<>]\n(a)"),
+        Err(Day10Error::InvalidCharacter(Position {
+            line: 2,
+            column: 2
+        }))
+    );
+    assert_eq!(
+        part_1("[<>]\n())"),
+        Err(Day10Error::UnexpectedCloser(Position {
+            line: 2,
+            column: 3
+        }))
+    );
+    assert_eq!(
+        part_2(&"(".repeat(STACK_SIZE + 1)),
+        Err(Day10Error::TooDeeplyNested(Position {
+            line: 1,
+            column: STACK_SIZE + 1
+        }))
+    );
+    assert_eq!(part_2("[<>]\n(]"), Err(Day10Error::NoIncompleteLines));
 }
 
 #[test]
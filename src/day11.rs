@@ -1,5 +1,34 @@
+//! Day 11: Dumbo Octopus. Simulates the flashing octopuses.
+//!
+//! ```
+//! use advent_of_code_2021::day11;
+//!
+//! let input = "5483143223\n2745854711\n5264556173\n6141336146\n6357385478
+//! 4167524645\n2176841721\n6882881134\n4846848554\n5283751526";
+//! assert_eq!(day11::part_1(input), Ok(1656));
+//! assert_eq!(day11::part_2(input), Ok(195));
+//! ```
+
 use std::fmt;
 
+use thiserror::Error;
+
+use crate::{
+    grid::{self, DigitMapError, Dynamic, Fixed, Size},
+    normalize,
+    position::Position,
+};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day11Error {
+    #[error("{0}: expected an energy level")]
+    ExpectedDigit(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: expected the end of the grid")]
+    ExpectedEnd(Position),
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 struct Octopus(u8);
 
@@ -27,18 +56,35 @@ impl Octopus {
     }
 }
 
-struct Grid<const SIZE: usize> {
+impl From<DigitMapError> for Day11Error {
+    fn from(error: DigitMapError) -> Self {
+        match error {
+            DigitMapError::ExpectedDigit(position) => Day11Error::ExpectedDigit(position),
+            DigitMapError::ExpectedNewline(position) => Day11Error::ExpectedNewline(position),
+            DigitMapError::ExpectedEnd(position) => Day11Error::ExpectedEnd(position),
+        }
+    }
+}
+
+/// The size of the grid in the puzzle input.
+type InputSize = Fixed<10, 10>;
+
+struct Grid<S: Size> {
     grid: Vec<Octopus>,
+    size: S,
     amount_of_flashes: usize,
+    /// The octopuses of which the energy level still has to be increased,
+    /// kept to reuse the allocation.
+    to_increase: Vec<(usize, usize)>,
 }
 
-impl<const SIZE: usize> fmt::Debug for Grid<SIZE> {
+impl<S: Size> fmt::Debug for Grid<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..SIZE {
-            for x in 0..SIZE {
+        for y in 0..self.size.height() {
+            for x in 0..self.size.width() {
                 write!(f, "{}", self.get_at(x, y).energy_level())?;
             }
-            if y < SIZE - 1 {
+            if y < self.size.height() - 1 {
                 writeln!(f)?;
             }
         }
@@ -46,50 +92,53 @@ impl<const SIZE: usize> fmt::Debug for Grid<SIZE> {
     }
 }
 
-impl<const SIZE: usize> Grid<SIZE> {
-    fn new(input: &str) -> Self {
-        Grid {
-            grid: input.bytes().map(|b| Octopus(b & 0b1111)).collect(),
+impl<S: Size> Grid<S> {
+    /// Parse a grid of energy levels of the given size. The input should have
+    /// `\n` line endings.
+    fn new(input: &str, size: S) -> Result<Self, Day11Error> {
+        let grid = grid::Grid::parse_digits(input, size)?;
+        Ok(Grid {
+            grid: grid
+                .as_bytes()
+                .iter()
+                .map(|b| Octopus(b & 0b1111))
+                .collect(),
+            size,
             amount_of_flashes: 0,
-        }
+            to_increase: Vec::new(),
+        })
     }
 
     fn get_at(&self, x: usize, y: usize) -> &Octopus {
-        &self.grid[y * (SIZE + 1) + x]
+        &self.grid[self.size.index(x, y)]
     }
 
     fn get_mut_at(&mut self, x: usize, y: usize) -> &mut Octopus {
-        &mut self.grid[y * (SIZE + 1) + x]
+        &mut self.grid[self.size.index(x, y)]
     }
 
     fn increase_energy_level_and_maybe_flash(&mut self, x: usize, y: usize) {
-        let octopus = self.get_mut_at(x, y);
-        let flashes = octopus.increase_energy_level();
-        if flashes {
-            self.amount_of_flashes += 1;
-            let min_x = x.saturating_sub(1);
-            let max_x = (x + 1).min(SIZE - 1);
-            let min_y = y.saturating_sub(1);
-            let max_y = (y + 1).min(SIZE - 1);
-            for other_x in min_x..=max_x {
-                for other_y in min_y..=max_y {
-                    if (other_x, other_y) != (x, y) {
-                        self.increase_energy_level_and_maybe_flash(other_x, other_y);
-                    }
-                }
+        // The flashes spread with a stack of octopuses to increase, as the
+        // recursion could get as deep as the grid is large
+        let size = self.size;
+        self.to_increase.push((x, y));
+        while let Some((x, y)) = self.to_increase.pop() {
+            if self.get_mut_at(x, y).increase_energy_level() {
+                self.amount_of_flashes += 1;
+                self.to_increase.extend(size.neighbours_8(x, y));
             }
         }
     }
 
     fn update(&mut self) -> bool {
-        for y in 0..SIZE {
-            for x in 0..SIZE {
+        for y in 0..self.size.height() {
+            for x in 0..self.size.width() {
                 self.increase_energy_level_and_maybe_flash(x, y);
             }
         }
         let mut all_flashes = true;
-        for y in 0..SIZE {
-            for x in 0..SIZE {
+        for y in 0..self.size.height() {
+            for x in 0..self.size.width() {
                 all_flashes = self.get_mut_at(x, y).reset_flashes() && all_flashes;
             }
         }
@@ -97,17 +146,43 @@ impl<const SIZE: usize> Grid<SIZE> {
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    let mut grid = Grid::<10>::new(input);
+/// Check that the input is a grid of energy levels with lines of equal
+/// length.
+pub fn parse(input: &str) -> Result<&[u8], Day11Error> {
+    Grid::new(input, Dynamic::of(input.as_bytes())).map(|_| input.as_bytes())
+}
+
+fn flashes_after_100_steps<S: Size>(input: &str, size: S) -> Result<usize, Day11Error> {
+    let mut grid = Grid::new(input, size)?;
     for _ in 0..100 {
         grid.update();
     }
-    grid.amount_of_flashes
+    Ok(grid.amount_of_flashes)
+}
+
+fn first_step_all_flash<S: Size>(input: &str, size: S) -> Result<usize, Day11Error> {
+    let mut grid = Grid::new(input, size)?;
+    Ok((0..).find(|_| grid.update()).unwrap() + 1)
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day11Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        flashes_after_100_steps(&input, InputSize::default())
+    } else {
+        flashes_after_100_steps(&input, size)
+    }
 }
 
-pub fn part_2(input: &str) -> usize {
-    let mut grid = Grid::<10>::new(input);
-    (0..).find(|_| grid.update()).unwrap() + 1
+pub fn part_2(input: &str) -> Result<usize, Day11Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        first_step_all_flash(&input, InputSize::default())
+    } else {
+        first_step_all_flash(&input, size)
+    }
 }
 
 #[test]
@@ -122,7 +197,7 @@ fn test_part_1_example() {
 6882881134
 4846848554
 5283751526";
-    assert_eq!(part_1(input), 1656);
+    assert_eq!(part_1(input), Ok(1656));
 }
 
 #[test]
@@ -137,7 +212,7 @@ fn test_part_2_example() {
 6882881134
 4846848554
 5283751526";
-    assert_eq!(part_2(input), 195);
+    assert_eq!(part_2(input), Ok(195));
 }
 
 #[test]
@@ -147,7 +222,7 @@ fn test_part_1_small_example() {
 19191
 19991
 11111";
-    let mut grid = Grid::<5>::new(input);
+    let mut grid = Grid::new(input, Fixed::<5, 5>).unwrap();
     assert_eq!(format!("{:?}", grid), input);
     grid.update();
     assert_eq!(
@@ -170,13 +245,46 @@ fn test_part_1_small_example() {
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day11.txt");
-    assert_eq!(part_1(input), 1617);
+fn test_parse_error() {
+    assert!(Grid::new("111\n191\n111", Fixed::<3, 3>).is_ok());
+    assert_eq!(
+        Grid::new("111\n1x1\n111", Fixed::<3, 3>).err(),
+        Some(Day11Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        Grid::new("111\n1911\n111", Fixed::<3, 3>).err(),
+        Some(Day11Error::ExpectedNewline(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        Grid::new("111\n191", Fixed::<3, 3>).err(),
+        Some(Day11Error::ExpectedNewline(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        Grid::new("111\n191\n111\n111", Fixed::<3, 3>).err(),
+        Some(Day11Error::ExpectedEnd(Position { line: 4, column: 1 }))
+    );
+    assert_eq!(
+        parse(""),
+        Err(Day11Error::ExpectedDigit(Position { line: 1, column: 1 }))
+    );
+    assert_eq!(
+        parse("111\n19\n111"),
+        Err(Day11Error::ExpectedDigit(Position { line: 2, column: 3 }))
+    );
+}
+
+#[test]
+fn test_non_square() {
+    // Every octopus flashes in the first step, and then again every tenth
+    let input = "99999\n99999";
+    assert_eq!(part_1(input), Ok(10 * 10));
+    assert_eq!(part_2(input), Ok(1));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day11.txt");
-    assert_eq!(part_2(input), 258);
+fn test_large_grid() {
+    // Every octopus flashes in the first step, in one chain of flashes
+    let line = "9".repeat(400);
+    let input = vec![line.as_str(); 400].join("\n");
+    assert_eq!(part_2(&input), Ok(1));
 }
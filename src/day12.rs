@@ -1,8 +1,36 @@
+//! Day 12: Passage Pathing. Counts the paths through the cave system.
+//!
+//! ```
+//! use advent_of_code_2021::day12;
+//!
+//! let input = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end";
+//! assert_eq!(day12::parse(input).unwrap()[0], ("start", "A"));
+//! assert_eq!(day12::part_1(input), Ok(10));
+//! assert_eq!(day12::part_2(input), Ok(36));
+//! ```
+
 use arrayvec::ArrayVec;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
 
 const MAX_NODES: usize = 10;
 const MAX_CONNECTIONS_PER_NODE: usize = 10;
 
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day12Error {
+    #[error("{0}: expected a cave name")]
+    ExpectedCave(Position),
+    #[error("{0}: expected '-'")]
+    ExpectedDash(Position),
+    #[error("{0}: there are more than {} caves", MAX_NODES)]
+    TooManyCaves(Position),
+    #[error("{0}: a cave has more than {} connections", MAX_CONNECTIONS_PER_NODE)]
+    TooManyConnections(Position),
+    #[error("there is no start cave")]
+    NoStart,
+}
+
 struct Graph<'a> {
     nodes: ArrayVec<(&'a [u8], Node<'a>), MAX_NODES>,
     visit_rule: VisitRule,
@@ -22,47 +50,54 @@ struct Node<'a> {
 }
 
 impl<'a> Graph<'a> {
-    fn add_connection_one_way(&mut self, name: &'a [u8], name_2: &'a [u8]) {
-        if name != b"end" && name_2 != b"start" {
-            let is_small = name.iter().all(|c| c.is_ascii_lowercase());
-            if let Some(existing_node) = self.get_node_mut(name) {
-                existing_node.neighbours.push(name_2);
-            } else {
-                let mut neighbours = ArrayVec::new();
-                neighbours.push(name_2);
-                self.nodes.push((
+    /// Add a connection from `name` to `name_2`, found on the given line of
+    /// the input.
+    fn add_connection_one_way(
+        &mut self,
+        name: &'a [u8],
+        name_2: &'a [u8],
+        line: usize,
+    ) -> Result<(), Day12Error> {
+        if name == b"end" {
+            return Ok(());
+        }
+        let position = Position { line, column: 1 };
+        if self.get_node(name).is_none() {
+            self.nodes
+                .try_push((
                     name,
                     Node {
-                        neighbours,
+                        neighbours: ArrayVec::new(),
                         visited: false,
-                        is_small,
+                        is_small: name.iter().all(|c| c.is_ascii_lowercase()),
                     },
-                ));
-            }
+                ))
+                .map_err(|_| Day12Error::TooManyCaves(position))?;
         }
+        if name_2 != b"start" {
+            self.get_node_mut(name)
+                .unwrap()
+                .neighbours
+                .try_push(name_2)
+                .map_err(|_| Day12Error::TooManyConnections(position))?;
+        }
+        Ok(())
     }
 
-    fn handle_line(&mut self, line: &'a [u8]) {
-        let dash_pos = memchr::memchr(b'-', line).unwrap();
-        let node_a_name = &line[..dash_pos];
-        let node_b_name = &line[(dash_pos + 1)..];
-        self.add_connection_one_way(node_a_name, node_b_name);
-        self.add_connection_one_way(node_b_name, node_a_name);
-    }
-
-    fn new(input: &'a str, visit_rule: VisitRule) -> Self {
+    fn new(input: &'a str, visit_rule: VisitRule) -> Result<Self, Day12Error> {
         let mut graph = Graph {
             nodes: ArrayVec::new(),
             visit_rule,
         };
-        let mut input = input.as_bytes();
-        while let Some(line_end_pos) = memchr::memchr(b'\n', input) {
-            let line = &input[..line_end_pos];
-            input = &input[line_end_pos + 1..];
-            graph.handle_line(line);
+        for (i, (node_a_name, node_b_name)) in parse(input)?.into_iter().enumerate() {
+            let (node_a_name, node_b_name) = (node_a_name.as_bytes(), node_b_name.as_bytes());
+            graph.add_connection_one_way(node_a_name, node_b_name, i + 1)?;
+            graph.add_connection_one_way(node_b_name, node_a_name, i + 1)?;
+        }
+        if graph.get_node(b"start").is_none() {
+            return Err(Day12Error::NoStart);
         }
-        graph.handle_line(input);
-        graph
+        Ok(graph)
     }
 
     fn get_node(&self, s: &[u8]) -> Option<&Node<'a>> {
@@ -128,12 +163,76 @@ impl<'a> Graph<'a> {
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    Graph::new(input, VisitRule::Single).paths_from_start_to_end()
+/// Parse the connections between the caves.
+pub fn parse(input: &str) -> Result<Vec<(&str, &str)>, Day12Error> {
+    let is_cave = |name: &str| !name.is_empty() && name.bytes().all(|c| c.is_ascii_alphabetic());
+    input
+        .split('\n')
+        .enumerate()
+        .map(|(y, line)| {
+            let error_at = |column| Position {
+                line: y + 1,
+                column: column + 1,
+            };
+            let dash_pos = memchr::memchr(b'-', line.as_bytes()).ok_or_else(|| {
+                let cave_len = line.bytes().take_while(u8::is_ascii_alphabetic).count();
+                if cave_len == 0 {
+                    Day12Error::ExpectedCave(error_at(0))
+                } else {
+                    Day12Error::ExpectedDash(error_at(cave_len))
+                }
+            })?;
+            let (node_a_name, node_b_name) = (&line[..dash_pos], &line[dash_pos + 1..]);
+            if !is_cave(node_a_name) {
+                return Err(Day12Error::ExpectedCave(error_at(0)));
+            }
+            if !is_cave(node_b_name) {
+                return Err(Day12Error::ExpectedCave(error_at(dash_pos + 1)));
+            }
+            Ok((node_a_name, node_b_name))
+        })
+        .collect()
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day12Error> {
+    let input = normalize::line_endings(input);
+    Graph::new(&input, VisitRule::Single).map(|mut graph| graph.paths_from_start_to_end())
 }
 
-pub fn part_2(input: &str) -> usize {
-    Graph::new(input, VisitRule::DoubleOnce).paths_from_start_to_end()
+pub fn part_2(input: &str) -> Result<usize, Day12Error> {
+    let input = normalize::line_endings(input);
+    Graph::new(&input, VisitRule::DoubleOnce).map(|mut graph| graph.paths_from_start_to_end())
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("start-A\nA-end"),
+        Ok(vec![("start", "A"), ("A", "end")])
+    );
+    assert_eq!(
+        parse("start-A\nA end"),
+        Err(Day12Error::ExpectedDash(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse("start-A\nA-"),
+        Err(Day12Error::ExpectedCave(Position { line: 2, column: 3 }))
+    );
+    assert_eq!(
+        parse("start-A\n-end"),
+        Err(Day12Error::ExpectedCave(Position { line: 2, column: 1 }))
+    );
+    let many_caves: Vec<_> = (b'a'..=b'k')
+        .map(|c| format!("start-{}", char::from(c)))
+        .collect();
+    assert_eq!(
+        part_1(&many_caves.join("\n")),
+        Err(Day12Error::TooManyCaves(Position {
+            line: 10,
+            column: 1
+        }))
+    );
+    assert_eq!(part_1("a-b\nb-end"), Err(Day12Error::NoStart));
 }
 
 #[test]
@@ -145,7 +244,7 @@ A-b
 b-d
 A-end
 b-end";
-    assert_eq!(part_1(input), 10);
+    assert_eq!(part_1(input), Ok(10));
 }
 
 #[test]
@@ -160,7 +259,7 @@ HN-end
 kj-sa
 kj-HN
 kj-dc";
-    assert_eq!(part_1(input), 19);
+    assert_eq!(part_1(input), Ok(19));
 }
 
 #[test]
@@ -183,7 +282,7 @@ he-WI
 zg-he
 pj-fs
 start-RW";
-    assert_eq!(part_1(input), 226);
+    assert_eq!(part_1(input), Ok(226));
 }
 
 #[test]
@@ -195,7 +294,7 @@ A-b
 b-d
 A-end
 b-end";
-    assert_eq!(part_2(input), 36);
+    assert_eq!(part_2(input), Ok(36));
 }
 
 #[test]
@@ -210,7 +309,7 @@ HN-end
 kj-sa
 kj-HN
 kj-dc";
-    assert_eq!(part_2(input), 103);
+    assert_eq!(part_2(input), Ok(103));
 }
 
 #[test]
@@ -233,17 +332,5 @@ he-WI
 zg-he
 pj-fs
 start-RW";
-    assert_eq!(part_2(input), 3509);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day12.txt");
-    assert_eq!(part_1(input), 5157);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day12.txt");
-    assert_eq!(part_2(input), 144309);
+    assert_eq!(part_2(input), Ok(3509));
 }
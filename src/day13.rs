@@ -1,4 +1,36 @@
-use std::{collections::HashSet, fmt, iter};
+//! Day 13: Transparent Origami. Folds the transparent paper to read the code.
+//!
+//! ```
+//! use advent_of_code_2021::day13;
+//!
+//! let input = "0,0\n4,0\n0,1\n\nfold along x=2";
+//! assert_eq!(day13::parse(input).unwrap().1, vec![(b'x', 2)]);
+//! assert_eq!(day13::part_1(input), Ok(2));
+//! ```
+
+use std::{collections::HashSet, fmt};
+
+use thiserror::Error;
+
+use crate::{
+    grid::{Dynamic, Grid, Size},
+    normalize,
+    position::Position,
+};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day13Error {
+    #[error("{0}: expected a number")]
+    ExpectedNumber(Position),
+    #[error("{0}: expected ','")]
+    ExpectedComma(Position),
+    #[error("{0}: expected 'fold along'")]
+    ExpectedFold(Position),
+    #[error("{0}: expected 'x=' or 'y='")]
+    ExpectedAxis(Position),
+    #[error("there are no fold instructions")]
+    NoFolds,
+}
 
 struct Points(HashSet<(u16, u16)>);
 
@@ -11,19 +43,18 @@ fn folded_points(
 
 impl fmt::Display for Points {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let max_x = self.0.iter().map(|p| p.0).max().unwrap_or_default();
-        let max_y = self.0.iter().map(|p| p.1).max().unwrap_or_default();
-        for y in 0..=max_y {
-            for x in 0..=max_x {
-                if self.0.contains(&(x, y)) {
-                    write!(f, "#")?;
-                } else {
-                    write!(f, ".")?;
-                }
-            }
-            writeln!(f)?;
+        let size = Dynamic {
+            width: usize::from(self.0.iter().map(|p| p.0).max().unwrap_or_default()) + 1,
+            height: usize::from(self.0.iter().map(|p| p.1).max().unwrap_or_default()) + 1,
+        };
+        let mut bytes = vec![b'.'; size.byte_len()];
+        for y in 0..size.height - 1 {
+            bytes[size.index(size.width, y)] = b'\n';
         }
-        Ok(())
+        for &(x, y) in &self.0 {
+            bytes[size.index(usize::from(x), usize::from(y))] = b'#';
+        }
+        writeln!(f, "{}", Grid::new(&bytes, size))
     }
 }
 
@@ -35,45 +66,94 @@ fn fold_point((x, y): (u16, u16), (axis, along): (u8, u16)) -> (u16, u16) {
     }
 }
 
-fn parse(
-    input: &str,
-) -> (
-    impl Iterator<Item = (u16, u16)> + '_,
-    impl Iterator<Item = (u8, u16)> + '_,
-) {
-    let mut parts = input.split("\n\n");
-    let point = parts.next().unwrap();
-    let instructions = parts.next().unwrap();
-
-    let points = point.lines().map(|coords| {
-        let mut coords = coords.trim_end().split(',');
-        let x = coords.next().unwrap().parse().unwrap();
-        let y = coords.next().unwrap().parse().unwrap();
-        (x, y)
-    });
-
-    let folds = instructions.lines().map(|line| {
-        let coord = &line["fold along ".len()..];
-        let axis = coord.as_bytes()[0];
-        let pos: u16 = coord[2..].parse().unwrap();
-        (axis, pos)
-    });
-
-    (points, folds)
+/// The dots and the fold instructions, as the axis (`b'x'` or `b'y'`) and the
+/// coordinate to fold along.
+pub type Manual = (Vec<(u16, u16)>, Vec<(u8, u16)>);
+
+pub fn parse(input: &str) -> Result<Manual, Day13Error> {
+    let input = &*normalize::line_endings(input);
+    // The position of a substring of the input
+    let position_of = |s: &str| {
+        Position::of(
+            input.as_bytes(),
+            s.as_ptr() as usize - input.as_ptr() as usize,
+        )
+    };
+    let number = |s: &str| {
+        s.parse()
+            .map_err(|_| Day13Error::ExpectedNumber(position_of(s)))
+    };
+    let (dots, instructions) = input.split_once("\n\n").ok_or(Day13Error::NoFolds)?;
+
+    let points = dots
+        .lines()
+        .map(|line| {
+            let line = line.trim_end();
+            let (x, y) = line
+                .split_once(',')
+                .ok_or_else(|| Day13Error::ExpectedComma(position_of(&line[line.len()..])))?;
+            Ok((number(x)?, number(y)?))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let folds: Vec<_> = instructions
+        .lines()
+        .map(|line| {
+            let coord = line
+                .strip_prefix("fold along ")
+                .ok_or_else(|| Day13Error::ExpectedFold(position_of(line)))?;
+            match coord.as_bytes() {
+                [axis @ (b'x' | b'y'), b'=', ..] => Ok((*axis, number(coord[2..].trim_end())?)),
+                _ => Err(Day13Error::ExpectedAxis(position_of(coord))),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    if folds.is_empty() {
+        return Err(Day13Error::NoFolds);
+    }
+
+    Ok((points, folds))
 }
 
-pub fn part_1(input: &str) -> usize {
-    let (points, mut folds) = parse(input);
-    folded_points(points, iter::once(folds.next().unwrap()))
+pub fn part_1(input: &str) -> Result<usize, Day13Error> {
+    let (points, folds) = parse(input)?;
+    Ok(folded_points(points.into_iter(), folds.into_iter().take(1))
         .0
-        .len()
+        .len())
 }
 
-pub fn part_2(input: &str) -> String {
-    let (points, folds) = parse(input);
-    let folds: Vec<_> = folds.collect();
-    let points = folded_points(points, folds.iter().copied());
-    format!("{}", points)
+pub fn part_2(input: &str) -> Result<String, Day13Error> {
+    let (points, folds) = parse(input)?;
+    let points = folded_points(points.into_iter(), folds.iter().copied());
+    Ok(format!("{}", points))
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("6,10\n0,14\n\nfold along y=7"),
+        Ok((vec![(6, 10), (0, 14)], vec![(b'y', 7)]))
+    );
+    assert_eq!(
+        parse("6,10\n0,x\n\nfold along y=7"),
+        Err(Day13Error::ExpectedNumber(Position { line: 2, column: 3 }))
+    );
+    assert_eq!(
+        parse("6,10\n0\n\nfold along y=7"),
+        Err(Day13Error::ExpectedComma(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse("6,10\n\nfold along y=7\nfold x=5"),
+        Err(Day13Error::ExpectedFold(Position { line: 4, column: 1 }))
+    );
+    assert_eq!(
+        parse("6,10\n\nfold along z=7"),
+        Err(Day13Error::ExpectedAxis(Position {
+            line: 3,
+            column: 12
+        }))
+    );
+    assert_eq!(parse("6,10\n0,14"), Err(Day13Error::NoFolds));
 }
 
 #[test]
@@ -99,7 +179,7 @@ fn test_part_1_example() {
 
 fold along y=7
 fold along x=5";
-    assert_eq!(part_1(input), 17);
+    assert_eq!(part_1(input), Ok(17));
 }
 
 #[test]
@@ -126,35 +206,13 @@ fn test_part_2_example() {
 fold along y=7
 fold along x=5";
     assert_eq!(
-        part_2(input),
-        "\
+        part_2(input).as_deref(),
+        Ok("\
 #####
 #...#
 #...#
 #...#
 #####
-"
-    );
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day13.txt");
-    assert_eq!(part_1(input), 631);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day13.txt");
-    assert_eq!(
-        part_2(input),
-        "\
-####.####.#....####...##..##..###..####
-#....#....#....#.......#.#..#.#..#.#...
-###..###..#....###.....#.#....#..#.###.
-#....#....#....#.......#.#.##.###..#...
-#....#....#....#....#..#.#..#.#.#..#...
-####.#....####.#.....##...###.#..#.#...
-"
+")
     );
 }
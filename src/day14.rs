@@ -1,18 +1,76 @@
+//! Day 14: Extended Polymerization. Grows the polymer and compares the most
+//! and the least common elements.
+//!
+//! ```
+//! use advent_of_code_2021::day14;
+//!
+//! let input = "NNCB
+//!
+//! CH -> B\nHH -> N\nCB -> H\nNH -> C\nHB -> C\nHC -> B\nHN -> C\nNN -> C
+//! BH -> H\nNC -> B\nNB -> B\nBN -> B\nBB -> N\nBC -> B\nCC -> N\nCN -> C";
+//! assert_eq!(day14::part_1(input), Ok(1588));
+//! assert_eq!(day14::part_2(input), Ok(2188189693529));
+//! ```
+
 #[cfg(test)]
 use std::collections::HashSet;
 
 use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
 
-fn parse(input: &str) -> (&[u8], impl Iterator<Item = (&[u8], u8)>) {
-    let mut split = input.split("\n\n");
-    let polymer = split.next().unwrap().as_bytes();
-    let iter = split.next().unwrap().lines().map(|l| {
-        let l = l.as_bytes();
-        let i = &l[..2];
-        let o = l[6];
-        (i, o)
-    });
-    (polymer, iter)
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day14Error {
+    #[error("{0}: expected an element, A to Z")]
+    ExpectedElement(Position),
+    #[error("{0}: expected ' -> '")]
+    ExpectedArrow(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("there are no pair insertion rules")]
+    NoRules,
+}
+
+/// The polymer template and the pair insertion rules.
+pub type Instructions<'a> = (&'a [u8], Vec<([u8; 2], u8)>);
+
+pub fn parse(input: &str) -> Result<Instructions<'_>, Day14Error> {
+    let bytes = input.as_bytes();
+    let error_at = |s: &[u8], offset: usize, error: fn(Position) -> Day14Error| {
+        error(Position::of(
+            bytes,
+            s.as_ptr() as usize - bytes.as_ptr() as usize + offset,
+        ))
+    };
+    let (polymer, rules) = input.split_once("\n\n").ok_or(Day14Error::NoRules)?;
+    let polymer = polymer.as_bytes();
+    if polymer.is_empty() {
+        return Err(Day14Error::ExpectedElement(Position { line: 1, column: 1 }));
+    }
+    if let Some(i) = polymer.iter().position(|b| !b.is_ascii_uppercase()) {
+        return Err(error_at(polymer, i, Day14Error::ExpectedElement));
+    }
+    let rules = rules
+        .lines()
+        .map(|l| {
+            let l = l.as_bytes();
+            let element = |i: usize| match l.get(i) {
+                Some(e) if e.is_ascii_uppercase() => Ok(*e),
+                _ => Err(error_at(l, i, Day14Error::ExpectedElement)),
+            };
+            let pair = [element(0)?, element(1)?];
+            if l.get(2..6) != Some(b" -> ") {
+                return Err(error_at(l, 2, Day14Error::ExpectedArrow));
+            }
+            let inserted = element(6)?;
+            if l.len() > 7 {
+                return Err(error_at(l, 7, Day14Error::ExpectedNewline));
+            }
+            Ok((pair, inserted))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((polymer, rules))
 }
 
 const MAX_INDEX: usize = 26 * 26;
@@ -22,17 +80,19 @@ fn pair_to_index(pair: &[u8]) -> usize {
         - (usize::from(b'A') * 26 + usize::from(b'A'))
 }
 
-fn solve(input: &str, rounds: usize) -> usize {
+fn solve(input: &str, rounds: usize) -> Result<usize, Day14Error> {
     let mut frequencies = [0usize; MAX_INDEX];
-    let (polymer, replacings) = parse(input);
+    let input = normalize::line_endings(input);
+    let (polymer, replacings) = parse(&input)?;
     let first_polymer_letter = polymer[0];
     for (pair_left, pair_right) in polymer.iter().tuple_windows() {
         frequencies[pair_to_index(&[*pair_left, *pair_right])] += 1;
     }
     let replacings: Vec<_> = replacings
+        .into_iter()
         .map(|(i, o)| {
             (
-                pair_to_index(i),
+                pair_to_index(&i),
                 pair_to_index(&[i[0], o]),
                 pair_to_index(&[o, i[1]]),
             )
@@ -56,19 +116,19 @@ fn solve(input: &str, rounds: usize) -> usize {
                 frequencies[pair_to_index(&[letter, other_letter])];
         }
     }
-    actual_letter_frequencies.iter().max().unwrap()
+    Ok(actual_letter_frequencies.iter().max().unwrap()
         - actual_letter_frequencies
             .iter()
             .filter(|l| **l != 0)
             .min()
-            .unwrap()
+            .unwrap())
 }
 
-pub fn part_1(input: &str) -> usize {
+pub fn part_1(input: &str) -> Result<usize, Day14Error> {
     solve(input, 10)
 }
 
-pub fn part_2(input: &str) -> usize {
+pub fn part_2(input: &str) -> Result<usize, Day14Error> {
     solve(input, 40)
 }
 
@@ -107,17 +167,33 @@ BB -> N
 BC -> B
 CC -> N
 CN -> C";
-    assert_eq!(part_1(input), 1588);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day14.txt");
-    assert_eq!(part_1(input), 2509);
+    assert_eq!(part_1(input), Ok(1588));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day14.txt");
-    assert_eq!(part_2(input), 2827627697643);
+fn test_parse_error() {
+    assert_eq!(
+        parse("NNCB\n\nCH -> B\nHH -> N"),
+        Ok((
+            b"NNCB".as_ref(),
+            vec![([b'C', b'H'], b'B'), ([b'H', b'H'], b'N')]
+        ))
+    );
+    assert_eq!(
+        parse("NNcB\n\nCH -> B"),
+        Err(Day14Error::ExpectedElement(Position { line: 1, column: 3 }))
+    );
+    assert_eq!(
+        parse("NNCB\n\nCH -> B\nHH => N"),
+        Err(Day14Error::ExpectedArrow(Position { line: 4, column: 3 }))
+    );
+    assert_eq!(
+        parse("NNCB\n\nCH -> B\nHH -> "),
+        Err(Day14Error::ExpectedElement(Position { line: 4, column: 7 }))
+    );
+    assert_eq!(
+        parse("NNCB\n\nCH -> BB"),
+        Err(Day14Error::ExpectedNewline(Position { line: 3, column: 8 }))
+    );
+    assert_eq!(parse("NNCB\n"), Err(Day14Error::NoRules));
 }
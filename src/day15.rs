@@ -1,143 +1,135 @@
+//! Day 15: Chiton. Finds the path with the lowest risk through the cave, and
+//! through the cave repeated five times in both directions.
+//!
+//! ```
+//! use advent_of_code_2021::day15;
+//!
+//! assert_eq!(day15::part_1("19\n11"), Ok(2));
+//! ```
+
 use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashMap},
 };
 
-struct Grid<'a, const SIZE: usize, const DUPLICATED: usize>(&'a [u8]);
-
-impl<'a, const SIZE: usize, const DUPLICATED: usize> Grid<'a, SIZE, DUPLICATED> {
-    fn new(input: &'a str) -> Self {
-        debug_assert_eq!(input.as_bytes()[SIZE], b'\n');
-        debug_assert_eq!(input.len(), (SIZE + 1) * SIZE - 1);
-        Grid(input.as_bytes())
-    }
+use thiserror::Error;
 
-    fn cost_at(&self, index: Index<SIZE, DUPLICATED>) -> usize {
-        (usize::from(self.0[index.array_index()] & 0b1111) + index.block_bonus() - 1) % 9 + 1
-    }
-}
-
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
-struct Index<const SIZE: usize, const DUPLICATED: usize> {
-    x: usize,
-    y: usize,
-}
+use crate::{
+    grid::{DigitMapError, Dynamic, Fixed, Grid, Size, Tiled},
+    normalize,
+    position::Position,
+};
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
-struct BinaryHeapItem<const SIZE: usize, const DUPLICATED: usize> {
-    index: Index<SIZE, DUPLICATED>,
-    cost_estimate: usize,
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day15Error {
+    #[error("{0}: expected a risk level")]
+    ExpectedDigit(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: expected the end of the grid")]
+    ExpectedEnd(Position),
 }
 
-impl<const SIZE: usize, const DUPLICATED: usize> Index<SIZE, DUPLICATED> {
-    fn from_x_y(x: usize, y: usize) -> Self {
-        Index { x, y }
-    }
+/// The size of the grid in the puzzle input.
+type InputSize = Fixed<100, 100>;
 
-    /// Guess the cost from this point to the goal
-    fn cost_guess(&self) -> usize {
-        (SIZE * DUPLICATED - 1 - self.x) + (SIZE * DUPLICATED - 1 - self.y)
+impl From<DigitMapError> for Day15Error {
+    fn from(error: DigitMapError) -> Self {
+        match error {
+            DigitMapError::ExpectedDigit(position) => Day15Error::ExpectedDigit(position),
+            DigitMapError::ExpectedNewline(position) => Day15Error::ExpectedNewline(position),
+            DigitMapError::ExpectedEnd(position) => Day15Error::ExpectedEnd(position),
+        }
     }
+}
 
-    fn array_index(&self) -> usize {
-        (self.x % SIZE) + (SIZE + 1) * (self.y % SIZE)
-    }
+/// The risk level at `(x, y)`, which increases by one for every tile to the
+/// right or down, wrapping around from 9 to 1.
+fn cost_at<S: Size, const DUPLICATED: usize>(
+    grid: &Tiled<'_, S, DUPLICATED>,
+    (x, y): (usize, usize),
+) -> usize {
+    let (tile_x, tile_y) = grid.tile(x, y);
+    (usize::from(grid.get(x, y) & 0b1111) + tile_x + tile_y - 1) % 9 + 1
+}
 
-    fn block_bonus(&self) -> usize {
-        (self.x / SIZE) + (self.y / SIZE)
-    }
+/// Guess the cost from this point to the goal
+fn cost_guess<S: Size>(size: S, (x, y): (usize, usize)) -> usize {
+    (size.width() - 1 - x) + (size.height() - 1 - y)
+}
 
-    fn neighbour_array_indices(&self) -> impl Iterator<Item = Index<SIZE, DUPLICATED>> {
-        // Left
-        (if self.x > 0 {
-            Some(Index {
-                x: self.x - 1,
-                y: self.y,
-            })
-        } else {
-            None
-        })
-        .into_iter()
-        // Right
-        .chain(
-            if self.x + 1 < SIZE * DUPLICATED {
-                Some(Index {
-                    x: self.x + 1,
-                    y: self.y,
-                })
-            } else {
-                None
-            }
-            .into_iter(),
-        )
-        // Up
-        .chain(
-            if self.y > 0 {
-                Some(Index {
-                    x: self.x,
-                    y: self.y - 1,
-                })
-            } else {
-                None
-            }
-            .into_iter(),
-        )
-        // Down
-        .chain(
-            if self.y + 1 < SIZE * DUPLICATED {
-                Some(Index {
-                    x: self.x,
-                    y: self.y + 1,
-                })
-            } else {
-                None
-            }
-            .into_iter(),
-        )
-    }
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+struct BinaryHeapItem {
+    index: (usize, usize),
+    cost_estimate: usize,
 }
 
-impl<const SIZE: usize, const DUPLICATED: usize> PartialOrd for BinaryHeapItem<SIZE, DUPLICATED> {
+impl PartialOrd for BinaryHeapItem {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<const SIZE: usize, const DUPLICATED: usize> Ord for BinaryHeapItem<SIZE, DUPLICATED> {
+impl Ord for BinaryHeapItem {
     fn cmp(&self, other: &Self) -> Ordering {
         (other.cost_estimate).cmp(&self.cost_estimate)
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    parts_generic::<100, 1>(input)
+/// Check that the input is a grid of risk levels with lines of equal length.
+pub fn parse(input: &str) -> Result<&[u8], Day15Error> {
+    let grid = Grid::parse_digits(input, Dynamic::of(input.as_bytes()))?;
+    Ok(grid.as_bytes())
 }
 
-pub fn part_2(input: &str) -> usize {
-    parts_generic::<100, 5>(input)
+pub fn part_1(input: &str) -> Result<usize, Day15Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        parts_generic::<_, 1>(&input, InputSize::default())
+    } else {
+        parts_generic::<_, 1>(&input, size)
+    }
+}
+
+pub fn part_2(input: &str) -> Result<usize, Day15Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        parts_generic::<_, 5>(&input, InputSize::default())
+    } else {
+        parts_generic::<_, 5>(&input, size)
+    }
 }
 
-pub fn parts_generic<const SIZE: usize, const DUPLICATED: usize>(input: &str) -> usize {
-    let grid = Grid::<SIZE, DUPLICATED>::new(input);
+/// The lowest total risk of a path through the grid of the given size,
+/// repeated `DUPLICATED` times in both directions. The input should have `\n`
+/// line endings.
+pub fn parts_generic<S: Size, const DUPLICATED: usize>(
+    input: &str,
+    size: S,
+) -> Result<usize, Day15Error> {
+    let grid = Grid::parse_digits(input, size)?.tiled::<DUPLICATED>();
+    let size = grid.size();
     let mut open_set = BinaryHeap::new();
     let mut g_score = HashMap::new();
-    let start: Index<SIZE, DUPLICATED> = Index::from_x_y(0, 0);
-    let end_index = Index::from_x_y(DUPLICATED * SIZE - 1, DUPLICATED * SIZE - 1);
+    let start = (0, 0);
+    let end_index = (size.width() - 1, size.height() - 1);
     open_set.push(BinaryHeapItem {
         index: start,
-        cost_estimate: start.cost_guess(),
+        cost_estimate: cost_guess(size, start),
     });
     g_score.insert(start, 0);
     while let Some(BinaryHeapItem { index, .. }) = open_set.pop() {
         let current_score = *g_score.get(&index).unwrap();
         if index == end_index {
-            return current_score;
+            return Ok(current_score);
         }
-        for neighbour in index.neighbour_array_indices() {
-            let tentative_score = current_score + grid.cost_at(neighbour);
+        for neighbour in size.neighbours_4(index.0, index.1) {
+            let tentative_score = current_score + cost_at(&grid, neighbour);
             if tentative_score < *g_score.get(&neighbour).unwrap_or(&usize::MAX) {
                 g_score.insert(neighbour, tentative_score);
-                let cost_estimate = tentative_score + neighbour.cost_guess();
+                let cost_estimate = tentative_score + cost_guess(size, neighbour);
                 open_set.push(BinaryHeapItem {
                     index: neighbour,
                     cost_estimate,
@@ -160,7 +152,8 @@ fn test_part_1_example() {
 3125421639
 1293138521
 2311944581";
-    assert_eq!(parts_generic::<10, 1>(input), 40);
+    assert_eq!(parts_generic::<_, 1>(input, Fixed::<10, 10>), Ok(40));
+    assert_eq!(part_1(input), Ok(40));
 }
 
 #[test]
@@ -175,17 +168,33 @@ fn test_part_2_example() {
 3125421639
 1293138521
 2311944581";
-    assert_eq!(parts_generic::<10, 5>(input), 315);
+    assert_eq!(parts_generic::<_, 5>(input, Fixed::<10, 10>), Ok(315));
+    assert_eq!(part_2(input), Ok(315));
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day15.txt");
-    assert_eq!(part_1(input), 423);
+fn test_parse_error() {
+    assert_eq!(parts_generic::<_, 1>("13\n21", Fixed::<2, 2>), Ok(3));
+    assert_eq!(
+        parts_generic::<_, 1>("13\n2", Fixed::<2, 2>),
+        Err(Day15Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parts_generic::<_, 1>("13 \n21", Fixed::<2, 2>),
+        Err(Day15Error::ExpectedNewline(Position { line: 1, column: 3 }))
+    );
+    assert_eq!(
+        parts_generic::<_, 1>("13\n21\n11", Fixed::<2, 2>),
+        Err(Day15Error::ExpectedEnd(Position { line: 3, column: 1 }))
+    );
+    assert_eq!(
+        parse("13\n2"),
+        Err(Day15Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day15.txt");
-    assert_eq!(part_2(input), 2778);
+fn test_non_square() {
+    assert_eq!(part_1("1191\n9111"), Ok(4));
+    assert_eq!(part_1("1\n2\n3"), Ok(5));
 }
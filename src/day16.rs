@@ -1,3 +1,33 @@
+//! Day 16: Packet Decoder. Decodes a BITS transmission into its outermost
+//! packet.
+//!
+//! ```
+//! use advent_of_code_2021::day16::{self, Packet};
+//!
+//! assert_eq!(
+//!     day16::parse("C200B40A82"),
+//!     Ok(Packet {
+//!         version_number_sum: 14,
+//!         value: 3
+//!     })
+//! );
+//! assert_eq!(day16::part_1("8A004A801A8002F478"), Ok(16));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day16Error {
+    #[error("{0}: expected a hexadecimal digit")]
+    InvalidHex(Position),
+    #[error("the transmission ends in the middle of a packet")]
+    UnexpectedEnd,
+    #[error("a literal value does not fit in 64 bits")]
+    LiteralTooLarge,
+}
+
 /// A struct representing a hexadecimal value.
 /// If least significant four bytes are 0,
 /// it has been depleted.
@@ -9,13 +39,13 @@
 struct Hex(u8);
 
 impl Hex {
-    fn from_hex_u8(hex: u8) -> Self {
-        Hex(0b0000_0001
-            | (match hex {
-                b'0'..=b'9' => hex - b'0',
-                b'A'..=b'F' => hex + 10 - b'A',
-                _ => panic!("invalid hex: {}", hex),
-            } << 4))
+    fn from_hex_u8(hex: u8) -> Option<Self> {
+        let value = match hex {
+            b'0'..=b'9' => hex - b'0',
+            b'A'..=b'F' => hex + 10 - b'A',
+            _ => return None,
+        };
+        Some(Hex(0b0000_0001 | value << 4))
     }
 
     fn empty() -> Self {
@@ -48,6 +78,11 @@ where
     remainder: T,
     /// How many bits have been read (or None's!)
     read: usize,
+    /// How many hexadecimal digits have been loaded
+    hex_read: usize,
+    /// The offset of the first invalid hexadecimal digit, at which reading
+    /// stopped
+    invalid_hex: Option<usize>,
 }
 
 impl<T> SliceHexReader<T>
@@ -61,11 +96,18 @@ where
     }
 
     fn load_next_hex(&mut self) {
-        self.current = self
-            .remainder
-            .next()
-            .map(Hex::from_hex_u8)
-            .unwrap_or_else(Hex::empty);
+        if self.invalid_hex.is_some() {
+            return;
+        }
+        self.current = match self.remainder.next().map(Hex::from_hex_u8) {
+            Some(Some(hex)) => hex,
+            Some(None) => {
+                self.invalid_hex = Some(self.hex_read);
+                Hex::empty()
+            }
+            None => Hex::empty(),
+        };
+        self.hex_read += 1;
     }
 
     fn new(remainder: T) -> Self {
@@ -73,8 +115,22 @@ where
             current: Hex::empty(),
             remainder,
             read: 0,
+            hex_read: 0,
+            invalid_hex: None,
         }
     }
+
+    /// Read a single bit, or report why there are none left.
+    fn next_bit(&mut self) -> Result<bool, Day16Error> {
+        self.next().ok_or(match self.invalid_hex {
+            // The transmission is a single line
+            Some(offset) => Day16Error::InvalidHex(Position {
+                line: 1,
+                column: offset + 1,
+            }),
+            None => Day16Error::UnexpectedEnd,
+        })
+    }
 }
 
 impl<T> Iterator for SliceHexReader<T>
@@ -94,36 +150,40 @@ where
 }
 
 /// Read a number
-fn number<T, Out>(input: &mut SliceHexReader<T>, len: usize) -> Out
+fn number<T, Out>(input: &mut SliceHexReader<T>, len: usize) -> Result<Out, Day16Error>
 where
     T: Iterator<Item = u8>,
     Out: num::Zero + num::One + std::ops::Shl<Output = Out> + std::ops::BitOr<Output = Out>,
 {
-    input.take(len).fold(Out::zero(), |acc, x| {
-        acc << Out::one() | if x { Out::one() } else { Out::zero() }
+    (0..len).try_fold(Out::zero(), |acc, _| {
+        let x = input.next_bit()?;
+        Ok(acc << Out::one() | if x { Out::one() } else { Out::zero() })
     })
 }
 
-fn value_block<T>(input: &mut SliceHexReader<T>) -> (bool, u8)
+fn value_block<T>(input: &mut SliceHexReader<T>) -> Result<(bool, u8), Day16Error>
 where
     T: Iterator<Item = u8>,
 {
-    let next = input.next().unwrap();
-    let value = number(input, 4);
-    (next, value)
+    let next = input.next_bit()?;
+    let value = number(input, 4)?;
+    Ok((next, value))
 }
 
-fn value<T>(input: &mut SliceHexReader<T>) -> usize
+fn value<T>(input: &mut SliceHexReader<T>) -> Result<usize, Day16Error>
 where
     T: Iterator<Item = u8>,
 {
-    let mut value = 0;
+    let mut value: usize = 0;
     loop {
-        let (cont, val_block) = value_block(input);
+        let (cont, val_block) = value_block(input)?;
+        if value.leading_zeros() < 4 {
+            return Err(Day16Error::LiteralTooLarge);
+        }
         value <<= 4;
         value |= usize::from(val_block);
         if !cont {
-            return value;
+            return Ok(value);
         }
     }
 }
@@ -135,22 +195,26 @@ struct PacketHeader {
 }
 
 impl PacketHeader {
-    fn read<T>(input: &mut SliceHexReader<T>) -> Self
+    fn read<T>(input: &mut SliceHexReader<T>) -> Result<Self, Day16Error>
     where
         T: Iterator<Item = u8>,
     {
-        let packet_version = number(input, 3);
-        let packet_type_id = number(input, 3);
-        PacketHeader {
+        let packet_version = number(input, 3)?;
+        let packet_type_id = number(input, 3)?;
+        Ok(PacketHeader {
             packet_version,
             packet_type_id,
-        }
+        })
     }
 }
 
-struct Packet {
-    version_number_sum: usize,
-    value: usize,
+/// A decoded packet, including its sub-packets.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet {
+    /// The sum of the versions of this packet and all of its sub-packets
+    pub version_number_sum: usize,
+    /// The value the packet evaluates to
+    pub value: usize,
 }
 
 trait PacketOperator {
@@ -258,11 +322,11 @@ impl PacketOperator for EqualOp {
 }
 
 impl Packet {
-    fn read<T>(input: &mut SliceHexReader<T>) -> Packet
+    fn read<T>(input: &mut SliceHexReader<T>) -> Result<Packet, Day16Error>
     where
         T: Iterator<Item = u8>,
     {
-        let header = PacketHeader::read(input);
+        let header = PacketHeader::read(input)?;
         match header.packet_type_id {
             0 => Packet::read_operator_packet::<_, SumOp>(input, header),
             1 => Packet::read_operator_packet::<_, ProductOp>(input, header),
@@ -276,27 +340,30 @@ impl Packet {
         }
     }
 
-    fn read_literal_value_packet<T>(input: &mut SliceHexReader<T>, header: PacketHeader) -> Packet
+    fn read_literal_value_packet<T>(
+        input: &mut SliceHexReader<T>,
+        header: PacketHeader,
+    ) -> Result<Packet, Day16Error>
     where
         T: Iterator<Item = u8>,
     {
-        let value = value(input);
+        let value = value(input)?;
         input.skip_hex_remainder();
         let version_number_sum = usize::from(header.packet_version);
-        Packet {
+        Ok(Packet {
             version_number_sum,
             value,
-        }
+        })
     }
 
     fn read_operator_packet<T, Op: PacketOperator>(
         input: &mut SliceHexReader<T>,
         header: PacketHeader,
-    ) -> Packet
+    ) -> Result<Packet, Day16Error>
     where
         T: Iterator<Item = u8>,
     {
-        let length_type_id = input.next().unwrap();
+        let length_type_id = input.next_bit()?;
 
         let inital_fold_value = (usize::from(header.packet_version), Op::first_value());
         let fold_fn = |(version_number_sum, value), packet: Packet| {
@@ -307,39 +374,38 @@ impl Packet {
         };
 
         let (version_number_sum, value) = if length_type_id {
-            let number_of_sub_packets: usize = number(input, 11);
+            let number_of_sub_packets: usize = number(input, 11)?;
             (0..number_of_sub_packets)
                 .map(|_| Packet::read(input))
-                .fold(inital_fold_value, fold_fn)
+                .try_fold(inital_fold_value, |acc, packet| Ok(fold_fn(acc, packet?)))?
         } else {
-            let total_bit_length = number::<_, usize>(input, 15) + input.read;
-            std::iter::from_fn(|| {
-                if input.read < total_bit_length {
-                    Some(Packet::read(input))
-                } else {
-                    None
-                }
-            })
-            .fold(inital_fold_value, fold_fn)
+            let total_bit_length = number::<_, usize>(input, 15)? + input.read;
+            let mut acc = inital_fold_value;
+            while input.read < total_bit_length {
+                acc = fold_fn(acc, Packet::read(input)?);
+            }
+            acc
         };
         input.skip_hex_remainder();
-        Packet {
+        Ok(Packet {
             version_number_sum,
             value,
-        }
+        })
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    let mut bits = SliceHexReader::new(input.bytes());
-    let packet = Packet::read(&mut bits);
-    packet.version_number_sum
+/// Decode the outermost packet of the hexadecimal transmission.
+pub fn parse(input: &str) -> Result<Packet, Day16Error> {
+    let input = normalize::line_endings(input);
+    Packet::read(&mut SliceHexReader::new(input.bytes()))
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day16Error> {
+    Ok(parse(input)?.version_number_sum)
 }
 
-pub fn part_2(input: &str) -> usize {
-    let mut bits = SliceHexReader::new(input.bytes());
-    let packet = Packet::read(&mut bits);
-    packet.value
+pub fn part_2(input: &str) -> Result<usize, Day16Error> {
+    Ok(parse(input)?.value)
 }
 
 #[test]
@@ -371,7 +437,7 @@ fn test_bit_decoding() {
 fn read_packet_header_test() {
     let packet = "D2FE28";
     let mut reader = SliceHexReader::new(packet.bytes());
-    let header = PacketHeader::read(&mut reader);
+    let header = PacketHeader::read(&mut reader).unwrap();
     assert_eq!(
         header,
         PacketHeader {
@@ -383,42 +449,51 @@ fn read_packet_header_test() {
 
 #[test]
 fn test_part_1_example_1() {
-    assert_eq!(part_1("8A004A801A8002F478"), 16);
+    assert_eq!(part_1("8A004A801A8002F478"), Ok(16));
 }
 
 #[test]
 fn test_part_1_example_2() {
-    assert_eq!(part_1("620080001611562C8802118E34"), 12);
+    assert_eq!(part_1("620080001611562C8802118E34"), Ok(12));
 }
 #[test]
 fn test_part_1_example_3() {
-    assert_eq!(part_1("C0015000016115A2E0802F182340"), 23);
+    assert_eq!(part_1("C0015000016115A2E0802F182340"), Ok(23));
 }
 #[test]
 fn test_part_1_example_4() {
-    assert_eq!(part_1("A0016C880162017C3686B18A3D4780"), 31);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day16.txt");
-    assert_eq!(part_1(input), 953);
+    assert_eq!(part_1("A0016C880162017C3686B18A3D4780"), Ok(31));
 }
 
 #[test]
 fn test_part_2_examples() {
-    assert_eq!(part_2("C200B40A82"), 3);
-    assert_eq!(part_2("04005AC33890"), 54);
-    assert_eq!(part_2("880086C3E88112"), 7);
-    assert_eq!(part_2("CE00C43D881120"), 9);
-    assert_eq!(part_2("D8005AC2A8F0"), 1);
-    assert_eq!(part_2("F600BC2D8F"), 0);
-    assert_eq!(part_2("9C005AC2F8F0"), 0);
-    assert_eq!(part_2("9C0141080250320F1802104A08"), 1);
+    assert_eq!(part_2("C200B40A82"), Ok(3));
+    assert_eq!(part_2("04005AC33890"), Ok(54));
+    assert_eq!(part_2("880086C3E88112"), Ok(7));
+    assert_eq!(part_2("CE00C43D881120"), Ok(9));
+    assert_eq!(part_2("D8005AC2A8F0"), Ok(1));
+    assert_eq!(part_2("F600BC2D8F"), Ok(0));
+    assert_eq!(part_2("9C005AC2F8F0"), Ok(0));
+    assert_eq!(part_2("9C0141080250320F1802104A08"), Ok(1));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day16.txt");
-    assert_eq!(part_2(input), 246225449979);
+fn test_parse_error() {
+    assert_eq!(
+        parse("D2FE28"),
+        Ok(Packet {
+            version_number_sum: 6,
+            value: 2021
+        })
+    );
+    assert_eq!(
+        parse("D2FE2G"),
+        Err(Day16Error::InvalidHex(Position { line: 1, column: 6 }))
+    );
+    assert_eq!(parse("D2FE"), Err(Day16Error::UnexpectedEnd));
+    assert_eq!(parse("38006F4529"), Err(Day16Error::UnexpectedEnd));
+    assert_eq!(
+        parse("D3FFFFFFFFFFFFFFFFFFFFE0"),
+        Err(Day16Error::LiteralTooLarge)
+    );
 }
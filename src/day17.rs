@@ -1,11 +1,34 @@
+//! Day 17: Trick Shot. Finds the velocities that make the probe hit the
+//! target area.
+//!
+//! ```
+//! use advent_of_code_2021::day17;
+//!
+//! let input = "target area: x=20..30, y=-10..-5";
+//! assert_eq!(day17::parse(input), Ok((20..=30, -10..=-5)));
+//! assert_eq!(day17::part_1(input), Ok(45));
+//! assert_eq!(day17::part_2(input), Ok(112));
+//! ```
+
 use nom::{
     bytes::complete::tag,
     character::complete::digit1,
-    combinator::{map, map_res, opt},
+    combinator::{all_consuming, map, map_res, opt, recognize},
     sequence::{pair, preceded, separated_pair},
 };
 use std::ops::RangeInclusive;
 use std::str::FromStr;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day17Error {
+    #[error("{0}: expected 'target area: x=<from>..<to>, y=<from>..<to>'")]
+    Parse(Position),
+    #[error("the target area should be non-empty and below the starting position")]
+    UnsupportedTarget,
+}
 
 struct Simulation {
     dy: i32,
@@ -30,8 +53,15 @@ impl Simulation {
     }
 }
 
-fn parse(input: &str) -> (RangeInclusive<u16>, RangeInclusive<i32>) {
-    preceded::<_, _, _, nom::error::Error<_>, _, _>(
+fn signed_number(input: &str) -> nom::IResult<&str, i32> {
+    map_res(recognize(pair(opt(tag("-")), digit1)), i32::from_str)(input)
+}
+
+/// Parse the target area, which should be below the probe's starting
+/// position.
+pub fn parse(input: &str) -> Result<(RangeInclusive<u16>, RangeInclusive<i32>), Day17Error> {
+    let input = &*normalize::line_endings(input);
+    let (x_range, y_range) = all_consuming(preceded(
         tag("target area: x="),
         separated_pair(
             map(
@@ -44,31 +74,21 @@ fn parse(input: &str) -> (RangeInclusive<u16>, RangeInclusive<i32>) {
             ),
             tag(", y="),
             map(
-                separated_pair(
-                    map(
-                        pair(opt(tag("-")), digit1),
-                        |(minus, num): (Option<&str>, &str)| {
-                            i32::from_str(num).unwrap() * if minus.is_some() { -1 } else { 1 }
-                        },
-                    ),
-                    tag(".."),
-                    map(
-                        pair(opt(tag("-")), digit1),
-                        |(minus, num): (Option<&str>, &str)| {
-                            i32::from_str(num).unwrap() * if minus.is_some() { -1 } else { 1 }
-                        },
-                    ),
-                ),
+                separated_pair(signed_number, tag(".."), signed_number),
                 |(a, b): (i32, i32)| a..=b,
             ),
         ),
-    )(input)
-    .unwrap()
-    .1
+    ))(input)
+    .map_err(|e| Day17Error::Parse(Position::of_nom_error(input.as_bytes(), &e)))?
+    .1;
+    if x_range.is_empty() || y_range.is_empty() || *y_range.end() >= 0 {
+        return Err(Day17Error::UnsupportedTarget);
+    }
+    Ok((x_range, y_range))
 }
 
-pub fn part_1(input: &str) -> i32 {
-    let (x_range, y_range) = &parse(input);
+pub fn part_1(input: &str) -> Result<i32, Day17Error> {
+    let (x_range, y_range) = &parse(input)?;
     // dx should be positive and it can't overshoot in the first step.
     let mut max_y = i32::MIN;
     for dx in 0..=*x_range.end() {
@@ -88,12 +108,12 @@ pub fn part_1(input: &str) -> i32 {
             }
         }
     }
-    max_y
+    Ok(max_y)
 }
 
-pub fn part_2(input: &str) -> usize {
+pub fn part_2(input: &str) -> Result<usize, Day17Error> {
     let mut count = 0;
-    let (x_range, y_range) = &parse(input);
+    let (x_range, y_range) = &parse(input)?;
     // dx should be positive and it can't overshoot in the first step.
     for dx in 0..=*x_range.end() {
         for dy in *y_range.start()..=-*y_range.start() {
@@ -110,27 +130,41 @@ pub fn part_2(input: &str) -> usize {
             }
         }
     }
-    count
+    Ok(count)
 }
 
 #[test]
 fn test_part_1_example() {
-    assert_eq!(part_1("target area: x=20..30, y=-10..-5"), 45);
+    assert_eq!(part_1("target area: x=20..30, y=-10..-5"), Ok(45));
 }
 
 #[test]
 fn test_part_2_example() {
-    assert_eq!(part_2("target area: x=20..30, y=-10..-5"), 112);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day17.txt");
-    assert_eq!(part_1(input), 10296);
+    assert_eq!(part_2("target area: x=20..30, y=-10..-5"), Ok(112));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day17.txt");
-    assert_eq!(part_2(input), 2371);
+fn test_parse_error() {
+    assert_eq!(
+        parse("target area: x=20..30, y=-10..-5"),
+        Ok((20..=30, -10..=-5))
+    );
+    assert_eq!(
+        parse("target area: x=20..30, y=-10..-"),
+        Err(Day17Error::Parse(Position {
+            line: 1,
+            column: 32
+        }))
+    );
+    assert_eq!(
+        parse("target area: x=20..30; y=-10..-5"),
+        Err(Day17Error::Parse(Position {
+            line: 1,
+            column: 22
+        }))
+    );
+    assert_eq!(
+        parse("target area: x=20..30, y=5..10"),
+        Err(Day17Error::UnsupportedTarget)
+    );
 }
@@ -0,0 +1,397 @@
+//! Day 18: Snailfish. Adds up snailfish numbers.
+//!
+//! ```
+//! use advent_of_code_2021::day18::{self, SnailfishNumber};
+//!
+//! let number: SnailfishNumber = "[[1,2],[[3,4],5]]".parse().unwrap();
+//! assert_eq!(number.magnitude(), 143);
+//! assert_eq!(day18::part_1("[[1,2],[[3,4],5]]\n[9,1]"), Ok(487));
+//! ```
+
+use std::{fmt, ops::Add, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+/// Pairs nested inside this many pairs explode.
+const MAX_DEPTH: u8 = 4;
+const SPLIT_THRESHOLD: u64 = 10;
+
+/// A regular number together with the amount of pairs it is nested in. Parsed
+/// values fit in a `u32`. Exploding and splitting never increase the sum of
+/// all values, so a `u64` can not overflow.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Element {
+    value: u64,
+    depth: u8,
+}
+
+/// A snailfish number, stored as its regular numbers from left to right.
+/// Because every pair has exactly two children, the depths are enough to
+/// recover the tree structure.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SnailfishNumber(Vec<Element>);
+
+#[derive(Error, Debug, Eq, PartialEq)]
+#[error("invalid snailfish number at byte {position}")]
+pub struct ParseSnailfishNumberError {
+    position: usize,
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day18Error {
+    #[error("{0}: invalid snailfish number")]
+    InvalidNumber(Position),
+    #[error("there are no snailfish numbers")]
+    NoNumbers,
+}
+
+impl FromStr for SnailfishNumber {
+    type Err = ParseSnailfishNumberError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /// What may come next.
+        #[derive(Eq, PartialEq)]
+        enum Expect {
+            Element,
+            Comma,
+            Close,
+            End,
+        }
+        let mut elements = Vec::new();
+        // Whether we are in the second element, for every pair we are in
+        let mut pairs: Vec<bool> = Vec::new();
+        let mut expect = Expect::Element;
+        let after_element = |pairs: &[bool]| match pairs.last() {
+            None => Expect::End,
+            Some(false) => Expect::Comma,
+            Some(true) => Expect::Close,
+        };
+        let mut bytes = s.trim_end().bytes().enumerate().peekable();
+        while let Some((position, b)) = bytes.next() {
+            match (&expect, b) {
+                // Leave room to add one to the depth when adding numbers
+                (Expect::Element, b'[') if pairs.len() < usize::from(u8::MAX - 1) => {
+                    pairs.push(false)
+                }
+                // The number itself should be a pair
+                (Expect::Element, b'0'..=b'9') if !pairs.is_empty() => {
+                    let mut value = u32::from(b - b'0');
+                    while let Some((_, digit @ b'0'..=b'9')) = bytes.peek().copied() {
+                        bytes.next();
+                        value = value
+                            .checked_mul(10)
+                            .and_then(|value| value.checked_add(u32::from(digit - b'0')))
+                            .ok_or(ParseSnailfishNumberError { position })?;
+                    }
+                    elements.push(Element {
+                        value: u64::from(value),
+                        depth: pairs.len() as u8,
+                    });
+                    expect = after_element(&pairs);
+                }
+                (Expect::Comma, b',') => {
+                    *pairs.last_mut().unwrap() = true;
+                    expect = Expect::Element;
+                }
+                (Expect::Close, b']') => {
+                    pairs.pop();
+                    expect = after_element(&pairs);
+                }
+                _ => return Err(ParseSnailfishNumberError { position }),
+            }
+        }
+        if expect != Expect::End {
+            return Err(ParseSnailfishNumberError {
+                position: s.trim_end().len(),
+            });
+        }
+        Ok(SnailfishNumber(elements))
+    }
+}
+
+impl SnailfishNumber {
+    /// Explode the leftmost pair that is nested too deep. Returns whether
+    /// anything changed.
+    fn explode(&mut self) -> bool {
+        let elements = &mut self.0;
+        if let Some(mut i) = elements.iter().position(|e| e.depth > MAX_DEPTH) {
+            // The leftmost element that is nested too deep is a left element.
+            // If the element after it is nested deeper, it is the first of the
+            // right element of the pair, which is a pair itself. After an
+            // addition of reduced numbers that never happens, but it may for
+            // other numbers.
+            while elements[i + 1].depth != elements[i].depth {
+                i += 1;
+            }
+            let left = elements[i].value;
+            let right = elements[i + 1].value;
+            if i > 0 {
+                elements[i - 1].value += left;
+            }
+            if let Some(next) = elements.get_mut(i + 2) {
+                next.value += right;
+            }
+            elements[i] = Element {
+                value: 0,
+                depth: elements[i].depth - 1,
+            };
+            elements.remove(i + 1);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Split the leftmost regular number that is too large. Returns whether
+    /// anything changed.
+    fn split(&mut self) -> bool {
+        let elements = &mut self.0;
+        if let Some(i) = elements.iter().position(|e| e.value >= SPLIT_THRESHOLD) {
+            let Element { value, depth } = elements[i];
+            elements[i] = Element {
+                value: value / 2,
+                depth: depth + 1,
+            };
+            elements.insert(
+                i + 1,
+                Element {
+                    value: value - value / 2,
+                    depth: depth + 1,
+                },
+            );
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Explode and split until neither applies anymore.
+    pub fn reduce(&mut self) {
+        while self.explode() || self.split() {}
+    }
+
+    pub fn magnitude(&self) -> usize {
+        fn magnitude_at(elements: &[Element], pos: &mut usize, depth: u8) -> usize {
+            if elements[*pos].depth == depth {
+                *pos += 1;
+                elements[*pos - 1].value as usize
+            } else {
+                3 * magnitude_at(elements, pos, depth + 1)
+                    + 2 * magnitude_at(elements, pos, depth + 1)
+            }
+        }
+        magnitude_at(&self.0, &mut 0, 0)
+    }
+}
+
+impl Add for SnailfishNumber {
+    type Output = SnailfishNumber;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.0.extend(rhs.0);
+        for element in self.0.iter_mut() {
+            element.depth += 1;
+        }
+        self.reduce();
+        self
+    }
+}
+
+impl fmt::Display for SnailfishNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_at(
+            f: &mut fmt::Formatter<'_>,
+            elements: &[Element],
+            pos: &mut usize,
+            depth: u8,
+        ) -> fmt::Result {
+            if elements[*pos].depth == depth {
+                *pos += 1;
+                write!(f, "{}", elements[*pos - 1].value)
+            } else {
+                write!(f, "[")?;
+                write_at(f, elements, pos, depth + 1)?;
+                write!(f, ",")?;
+                write_at(f, elements, pos, depth + 1)?;
+                write!(f, "]")
+            }
+        }
+        write_at(f, &self.0, &mut 0, 0)
+    }
+}
+
+/// Parse the homework assignment, one snailfish number per line.
+pub fn parse(input: &str) -> Result<Vec<SnailfishNumber>, Day18Error> {
+    normalize::line_endings(input)
+        .lines()
+        .enumerate()
+        .map(|(y, line)| {
+            line.parse().map_err(|e: ParseSnailfishNumberError| {
+                Day18Error::InvalidNumber(Position {
+                    line: y + 1,
+                    column: e.position + 1,
+                })
+            })
+        })
+        .collect()
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day18Error> {
+    parse(input)?
+        .into_iter()
+        .reduce(Add::add)
+        .map(|sum| sum.magnitude())
+        .ok_or(Day18Error::NoNumbers)
+}
+
+pub fn part_2(input: &str) -> Result<usize, Day18Error> {
+    let numbers = parse(input)?;
+    if numbers.is_empty() {
+        return Err(Day18Error::NoNumbers);
+    }
+    let mut max = 0;
+    for (i, a) in numbers.iter().enumerate() {
+        for (j, b) in numbers.iter().enumerate() {
+            if i != j {
+                max = max.max((a.clone() + b.clone()).magnitude());
+            }
+        }
+    }
+    Ok(max)
+}
+
+#[cfg(test)]
+fn reduced(s: &str) -> String {
+    let mut number: SnailfishNumber = s.parse().unwrap();
+    number.reduce();
+    number.to_string()
+}
+
+#[test]
+fn test_display_round_trip() {
+    for s in [
+        "[1,2]",
+        "[[1,2],3]",
+        "[9,[8,7]]",
+        "[[1,9],[8,5]]",
+        "[[[[1,2],[3,4]],[[5,6],[7,8]]],9]",
+        "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]",
+        "[[[[0,7],4],[15,[0,13]]],[1,1]]",
+    ] {
+        assert_eq!(s.parse::<SnailfishNumber>().unwrap().to_string(), s);
+    }
+}
+
+#[test]
+fn test_parse_invalid() {
+    assert!("[1,2".parse::<SnailfishNumber>().is_err());
+    assert!("[1,2]]".parse::<SnailfishNumber>().is_err());
+    assert!("[1,x]".parse::<SnailfishNumber>().is_err());
+    let error = |s: &str| s.parse::<SnailfishNumber>().unwrap_err().position;
+    assert_eq!(error("[[1]]"), 3);
+    assert_eq!(error("1,2"), 0);
+    assert_eq!(error("[1,2,3]"), 4);
+    assert_eq!(error("[1,2][3,4]"), 5);
+    assert_eq!(error("[1,[2,]]"), 6);
+    assert_eq!(error(""), 0);
+    assert_eq!(error("[1,4294967296]"), 3);
+    assert_eq!(error(&"[".repeat(300)), 254);
+    assert_eq!(
+        parse("[1,2]\n[[3,4],x]"),
+        Err(Day18Error::InvalidNumber(Position { line: 2, column: 8 }))
+    );
+    assert_eq!(part_1(""), Err(Day18Error::NoNumbers));
+}
+
+#[test]
+fn test_explode() {
+    assert_eq!(reduced("[[[[[9,8],1],2],3],4]"), "[[[[0,9],2],3],4]");
+    assert_eq!(reduced("[7,[6,[5,[4,[3,2]]]]]"), "[7,[6,[5,[7,0]]]]");
+    assert_eq!(reduced("[[6,[5,[4,[3,2]]]],1]"), "[[6,[5,[7,0]]],3]");
+    assert_eq!(
+        reduced("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"),
+        "[[3,[2,[8,0]]],[9,[5,[7,0]]]]"
+    );
+}
+
+#[test]
+fn test_reduce_unreduced() {
+    assert_eq!(reduced("[[[[[1,[2,3]],4],5],6],7]"), "[[[[0,7],5],6],7]");
+    let mut number: SnailfishNumber = "[[[[[1,4294967295],4294967295],1],1],1]".parse().unwrap();
+    assert!(number.explode());
+    assert_eq!(number.to_string(), "[[[[0,8589934590],1],1],1]");
+}
+
+#[test]
+fn test_add() {
+    let a: SnailfishNumber = "[[[[4,3],4],4],[7,[[8,4],9]]]".parse().unwrap();
+    let b: SnailfishNumber = "[1,1]".parse().unwrap();
+    assert_eq!((a + b).to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+}
+
+#[test]
+fn test_sum() {
+    let input = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
+[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
+[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]
+[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]
+[7,[5,[[3,8],[1,4]]]]
+[[2,[2,2]],[8,[8,1]]]
+[2,9]
+[1,[[[9,3],9],[[9,0],[0,7]]]]
+[[[5,[7,4]],7],1]
+[[[[4,2],2],6],[8,7]]";
+    assert_eq!(
+        parse(input)
+            .unwrap()
+            .into_iter()
+            .reduce(Add::add)
+            .unwrap()
+            .to_string(),
+        "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]"
+    );
+}
+
+#[test]
+fn test_magnitude() {
+    let magnitude = |s: &str| s.parse::<SnailfishNumber>().unwrap().magnitude();
+    assert_eq!(magnitude("[[1,2],[[3,4],5]]"), 143);
+    assert_eq!(magnitude("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"), 1384);
+    assert_eq!(magnitude("[[[[1,1],[2,2]],[3,3]],[4,4]]"), 445);
+    assert_eq!(
+        magnitude("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]"),
+        3488
+    );
+}
+
+#[test]
+fn test_part_1_example() {
+    let input = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+    assert_eq!(part_1(input), Ok(4140));
+}
+
+#[test]
+fn test_part_2_example() {
+    let input = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+    assert_eq!(part_2(input), Ok(3993));
+}
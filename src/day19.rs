@@ -0,0 +1,669 @@
+//! Day 19: Beacon Scanner. Aligns the reports of the scanners to map the
+//! beacons.
+//!
+//! ```
+//! use advent_of_code_2021::day19;
+//!
+//! let input = "--- scanner 0 ---\n0,2,0\n4,1,0\n3,3,0";
+//! assert_eq!(day19::parse(input).unwrap()[0].beacons().len(), 3);
+//! assert_eq!(day19::part_1(input), Ok(3));
+//! assert_eq!(day19::part_2(input), Ok(0));
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[cfg(test)]
+use rand::{Rng, SeedableRng};
+#[cfg(test)]
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+pub type Point = [i32; 3];
+
+/// The minimum number of beacons two scanners must share to be aligned.
+const OVERLAP: usize = 12;
+/// The number of pairwise distances among the shared beacons.
+const OVERLAP_DISTANCES: usize = OVERLAP * (OVERLAP - 1) / 2;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day19Error {
+    #[error("{0}: expected '--- scanner <number> ---'")]
+    ExpectedHeader(Position),
+    #[error("{0}: expected scanner {1}")]
+    UnexpectedScannerNumber(Position, usize),
+    #[error("{0}: expected a coordinate")]
+    ExpectedCoordinate(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("there are no scanners")]
+    NoScanners,
+    #[error("some scanner does not overlap with the others")]
+    UnalignedScanner,
+}
+
+fn add(a: Point, b: Point) -> Point {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn manhattan_distance(a: Point, b: Point) -> i32 {
+    sub(a, b).iter().map(|d| d.abs()).sum()
+}
+
+/// A rotation that maps the grid onto itself: coordinate `i` of the result is
+/// coordinate `axes[i]` of the input, multiplied by `signs[i]`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Rotation {
+    axes: [usize; 3],
+    signs: [i32; 3],
+}
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation {
+        axes: [0, 1, 2],
+        signs: [1, 1, 1],
+    };
+
+    /// All 24 proper rotations, i.e. the signed permutations with
+    /// determinant 1. Mirror images are excluded.
+    pub fn all() -> impl Iterator<Item = Rotation> {
+        const PERMUTATIONS: [([usize; 3], i32); 6] = [
+            ([0, 1, 2], 1),
+            ([1, 2, 0], 1),
+            ([2, 0, 1], 1),
+            ([0, 2, 1], -1),
+            ([2, 1, 0], -1),
+            ([1, 0, 2], -1),
+        ];
+        PERMUTATIONS.into_iter().flat_map(|(axes, parity)| {
+            (0..8)
+                .map(move |bits| {
+                    let sign = |bit: usize| if bits & (1 << bit) == 0 { 1 } else { -1 };
+                    Rotation {
+                        axes,
+                        signs: [sign(0), sign(1), sign(2)],
+                    }
+                })
+                .filter(move |r| parity * r.signs.iter().product::<i32>() == 1)
+        })
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        [
+            self.signs[0] * p[self.axes[0]],
+            self.signs[1] * p[self.axes[1]],
+            self.signs[2] * p[self.axes[2]],
+        ]
+    }
+
+    /// The rotation that first applies `self` and then `other`.
+    pub fn then(&self, other: Rotation) -> Rotation {
+        let mut result = Rotation::IDENTITY;
+        for i in 0..3 {
+            result.axes[i] = self.axes[other.axes[i]];
+            result.signs[i] = other.signs[i] * self.signs[other.axes[i]];
+        }
+        result
+    }
+
+    pub fn inverse(&self) -> Rotation {
+        let mut result = Rotation::IDENTITY;
+        for i in 0..3 {
+            result.axes[self.axes[i]] = i;
+            result.signs[self.axes[i]] = self.signs[i];
+        }
+        result
+    }
+}
+
+/// A rotation followed by a translation, mapping the coordinates of one
+/// scanner into those of another.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub translation: Point,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        rotation: Rotation::IDENTITY,
+        translation: [0, 0, 0],
+    };
+
+    pub fn apply(&self, p: Point) -> Point {
+        add(self.rotation.apply(p), self.translation)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Scanner {
+    beacons: Vec<Point>,
+    /// The sorted squared distances between every pair of beacons. These are
+    /// invariant under rotation and translation.
+    fingerprint: Vec<i32>,
+}
+
+impl Scanner {
+    pub fn new(beacons: Vec<Point>) -> Self {
+        let mut fingerprint = Vec::with_capacity(beacons.len() * beacons.len() / 2);
+        for (i, a) in beacons.iter().enumerate() {
+            for b in &beacons[i + 1..] {
+                fingerprint.push(sub(*a, *b).iter().map(|d| d * d).sum());
+            }
+        }
+        fingerprint.sort_unstable();
+        Scanner {
+            beacons,
+            fingerprint,
+        }
+    }
+
+    pub fn beacons(&self) -> &[Point] {
+        &self.beacons
+    }
+
+    fn transformed(&self, transform: Transform) -> Self {
+        Scanner {
+            beacons: self.beacons.iter().map(|p| transform.apply(*p)).collect(),
+            fingerprint: self.fingerprint.clone(),
+        }
+    }
+
+    /// Count the pairwise distances both scanners have in common.
+    fn shared_distances(&self, other: &Scanner) -> usize {
+        let (mut a, mut b) = (self.fingerprint.iter(), other.fingerprint.iter());
+        let (mut x, mut y) = (a.next(), b.next());
+        let mut shared = 0;
+        while let (Some(dx), Some(dy)) = (x, y) {
+            if dx < dy {
+                x = a.next();
+            } else if dy < dx {
+                y = b.next();
+            } else {
+                shared += 1;
+                x = a.next();
+                y = b.next();
+            }
+        }
+        shared
+    }
+
+    /// Find the transform that maps the beacons of `other` onto at least
+    /// twelve beacons of `self`.
+    pub fn align(&self, other: &Scanner) -> Option<Transform> {
+        if self.shared_distances(other) < OVERLAP_DISTANCES {
+            return None;
+        }
+        let mut offsets = HashMap::new();
+        for rotation in Rotation::all() {
+            offsets.clear();
+            for b in other.beacons.iter().map(|b| rotation.apply(*b)) {
+                for a in self.beacons.iter() {
+                    let translation = sub(*a, b);
+                    let count = offsets.entry(translation).or_insert(0);
+                    *count += 1;
+                    if *count == OVERLAP {
+                        return Some(Transform {
+                            rotation,
+                            translation,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Find the transform of every scanner into the coordinates of the first one.
+/// Returns `None` if there are no scanners or some scanner could not be
+/// aligned.
+pub fn locate_scanners(scanners: &[Scanner]) -> Option<Vec<Transform>> {
+    if scanners.is_empty() {
+        return None;
+    }
+    let mut transforms = vec![None; scanners.len()];
+    let mut aligned = vec![None; scanners.len()];
+    let mut queue = VecDeque::new();
+    transforms[0] = Some(Transform::IDENTITY);
+    aligned[0] = Some(scanners[0].clone());
+    queue.push_back(0);
+    while let Some(reference) = queue.pop_front() {
+        for (other, scanner) in scanners.iter().enumerate() {
+            if transforms[other].is_some() {
+                continue;
+            }
+            let reference_scanner = aligned[reference].as_ref().unwrap();
+            if let Some(transform) = reference_scanner.align(scanner) {
+                transforms[other] = Some(transform);
+                aligned[other] = Some(scanner.transformed(transform));
+                queue.push_back(other);
+            }
+        }
+    }
+    transforms.into_iter().collect()
+}
+
+/// Parse the reports of all scanners.
+pub fn parse(input: &str) -> Result<Vec<Scanner>, Day19Error> {
+    let input = normalize::line_endings(input);
+    let mut scanners = Vec::new();
+    let mut beacons: Option<Vec<Point>> = None;
+    for (y, line) in input.lines().enumerate() {
+        let line = line.trim_end();
+        let error_at = |column| Position {
+            line: y + 1,
+            column: column + 1,
+        };
+        match &mut beacons {
+            None => {
+                const PREFIX: &str = "--- scanner ";
+                let header = line
+                    .strip_prefix(PREFIX)
+                    .ok_or(Day19Error::ExpectedHeader(error_at(0)))?;
+                let digits = header.bytes().take_while(u8::is_ascii_digit).count();
+                match header[..digits].parse::<usize>() {
+                    Ok(number) if number == scanners.len() => {}
+                    Ok(_) => {
+                        return Err(Day19Error::UnexpectedScannerNumber(
+                            error_at(PREFIX.len()),
+                            scanners.len(),
+                        ))
+                    }
+                    Err(_) => return Err(Day19Error::ExpectedHeader(error_at(PREFIX.len()))),
+                }
+                if &header[digits..] != " ---" {
+                    return Err(Day19Error::ExpectedHeader(error_at(PREFIX.len() + digits)));
+                }
+                beacons = Some(Vec::new());
+            }
+            Some(_) if line.is_empty() => scanners.push(Scanner::new(beacons.take().unwrap())),
+            Some(beacons) => {
+                let mut beacon = [0; 3];
+                let mut coordinates = line.split(',');
+                let mut column = 0;
+                for c in beacon.iter_mut() {
+                    let coordinate = coordinates
+                        .next()
+                        .ok_or_else(|| Day19Error::ExpectedCoordinate(error_at(line.len())))?;
+                    *c = coordinate
+                        .parse()
+                        .map_err(|_| Day19Error::ExpectedCoordinate(error_at(column)))?;
+                    column += coordinate.len() + 1;
+                }
+                if coordinates.next().is_some() {
+                    return Err(Day19Error::ExpectedNewline(error_at(column - 1)));
+                }
+                beacons.push(beacon);
+            }
+        }
+    }
+    scanners.extend(beacons.map(Scanner::new));
+    if scanners.is_empty() {
+        return Err(Day19Error::NoScanners);
+    }
+    Ok(scanners)
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day19Error> {
+    let scanners = parse(input)?;
+    let transforms = locate_scanners(&scanners).ok_or(Day19Error::UnalignedScanner)?;
+    Ok(scanners
+        .iter()
+        .zip(transforms)
+        .flat_map(|(scanner, transform)| scanner.beacons.iter().map(move |b| transform.apply(*b)))
+        .collect::<HashSet<_>>()
+        .len())
+}
+
+pub fn part_2(input: &str) -> Result<i32, Day19Error> {
+    let transforms = locate_scanners(&parse(input)?).ok_or(Day19Error::UnalignedScanner)?;
+    let mut max = 0;
+    for a in transforms.iter() {
+        for b in transforms.iter() {
+            max = max.max(manhattan_distance(a.translation, b.translation));
+        }
+    }
+    Ok(max)
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
+/// Build a report for a made-up beacon field, together with the expected
+/// answers for both parts.
+#[cfg(test)]
+fn synthetic_report() -> (String, usize, i32) {
+    const RANGE: i32 = 1000;
+    let positions: [Point; 5] = [
+        [0, 0, 0],
+        [1105, -68, 20],
+        [1160, 1180, -120],
+        [2250, 1010, 95],
+        [-90, 40, 1160],
+    ];
+    let links = [(0, 1), (1, 2), (2, 3), (0, 4)];
+
+    let mut rng = ChaCha8Rng::seed_from_u64(2021);
+    let mut random = |range: i32| rng.gen_range(-range, range + 1);
+    let in_range = |p: Point, s: Point| sub(p, s).iter().all(|d| d.abs() <= RANGE);
+
+    let mut beacons = HashSet::new();
+    // Make sure linked scanners have enough beacons in common
+    for (a, b) in links {
+        let centre = [
+            (positions[a][0] + positions[b][0]) / 2,
+            (positions[a][1] + positions[b][1]) / 2,
+            (positions[a][2] + positions[b][2]) / 2,
+        ];
+        let mut shared = 0;
+        while shared < OVERLAP + 3 {
+            let p = add(centre, [random(350), random(350), random(350)]);
+            if in_range(p, positions[a]) && in_range(p, positions[b]) && beacons.insert(p) {
+                shared += 1;
+            }
+        }
+    }
+    for position in positions {
+        for _ in 0..12 {
+            beacons.insert(add(position, [random(RANGE), random(RANGE), random(RANGE)]));
+        }
+    }
+
+    let mut report = String::new();
+    let mut seen = HashSet::new();
+    for (i, (position, rotation)) in positions.iter().zip(Rotation::all().step_by(5)).enumerate() {
+        if i > 0 {
+            report.push('\n');
+        }
+        report.push_str(&format!("--- scanner {} ---\n", i));
+        for beacon in beacons.iter().filter(|b| in_range(**b, *position)) {
+            seen.insert(*beacon);
+            let [x, y, z] = rotation.inverse().apply(sub(*beacon, *position));
+            report.push_str(&format!("{},{},{}\n", x, y, z));
+        }
+    }
+    let max_distance = positions
+        .iter()
+        .flat_map(|a| positions.iter().map(move |b| manhattan_distance(*a, *b)))
+        .max()
+        .unwrap();
+    (report, seen.len(), max_distance)
+}
+
+#[test]
+fn test_rotations() {
+    let rotations: HashSet<Rotation> = Rotation::all().collect();
+    assert_eq!(rotations.len(), 24);
+    let p = [1, 2, 3];
+    let images: HashSet<Point> = rotations.iter().map(|r| r.apply(p)).collect();
+    assert_eq!(images.len(), 24);
+    for a in rotations.iter() {
+        assert_eq!(a.then(a.inverse()), Rotation::IDENTITY);
+        assert_eq!(a.inverse().apply(a.apply(p)), p);
+        for b in rotations.iter() {
+            let composed = a.then(*b);
+            assert!(rotations.contains(&composed));
+            assert_eq!(composed.apply(p), b.apply(a.apply(p)));
+        }
+    }
+}
+
+#[test]
+fn test_align() {
+    let beacons: Vec<Point> = (0..15)
+        .map(|i| [i * i, 3 * i - 7, 100 - 5 * i * i])
+        .collect();
+    let transform = Transform {
+        rotation: Rotation::all().nth(13).unwrap(),
+        translation: [-40, 17, 900],
+    };
+    let reference = Scanner::new(beacons.clone());
+    let other = Scanner::new(
+        beacons
+            .iter()
+            .map(|b| {
+                transform
+                    .rotation
+                    .inverse()
+                    .apply(sub(*b, transform.translation))
+            })
+            .collect(),
+    );
+    assert_eq!(reference.align(&other), Some(transform));
+}
+
+#[test]
+fn test_parse() {
+    let scanners = parse(
+        "--- scanner 0 ---
+-1,-1,1
+-2,-2,2
+
+--- scanner 1 ---
+1,-1,1
+",
+    )
+    .unwrap();
+    assert_eq!(scanners.len(), 2);
+    assert_eq!(scanners[0].beacons(), &[[-1, -1, 1], [-2, -2, 2]]);
+    assert_eq!(scanners[1].beacons(), &[[1, -1, 1]]);
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("--- scanner 0 ---\n-1,-1,1\n-2,x,2").err(),
+        Some(Day19Error::ExpectedCoordinate(Position {
+            line: 3,
+            column: 4
+        }))
+    );
+    assert_eq!(
+        parse("--- scanner 0 ---\n-1,-1").err(),
+        Some(Day19Error::ExpectedCoordinate(Position {
+            line: 2,
+            column: 6
+        }))
+    );
+    assert_eq!(
+        parse("--- scanner 0 ---\n-1,-1,1,1").err(),
+        Some(Day19Error::ExpectedNewline(Position { line: 2, column: 8 }))
+    );
+    assert_eq!(
+        parse("--- scanner 0 ---\n-1,-1,1\n\n1,2,3").err(),
+        Some(Day19Error::ExpectedHeader(Position { line: 4, column: 1 }))
+    );
+    assert_eq!(
+        parse("--- scanner x ---").err(),
+        Some(Day19Error::ExpectedHeader(Position {
+            line: 1,
+            column: 13
+        }))
+    );
+    assert_eq!(
+        parse("--- scanner 0 --").err(),
+        Some(Day19Error::ExpectedHeader(Position {
+            line: 1,
+            column: 14
+        }))
+    );
+    assert_eq!(
+        parse("--- scanner 0 ---\n-1,-1,1\n\n--- scanner 2 ---\n1,2,3").err(),
+        Some(Day19Error::UnexpectedScannerNumber(
+            Position {
+                line: 4,
+                column: 13
+            },
+            1
+        ))
+    );
+    assert_eq!(parse("").err(), Some(Day19Error::NoScanners));
+    assert_eq!(locate_scanners(&[]), None);
+    assert_eq!(
+        part_1("--- scanner 0 ---\n-1,-1,1\n\n--- scanner 1 ---\n1,2,3"),
+        Err(Day19Error::UnalignedScanner)
+    );
+}
+
+#[test]
+fn test_part_1_example() {
+    assert_eq!(part_1(EXAMPLE), Ok(79));
+}
+
+#[test]
+fn test_part_2_example() {
+    assert_eq!(part_2(EXAMPLE), Ok(3621));
+}
+
+#[test]
+fn test_part_1_synthetic() {
+    let (input, beacons, _) = synthetic_report();
+    assert_eq!(part_1(&input), Ok(beacons));
+}
+
+#[test]
+fn test_part_2_synthetic() {
+    let (input, _, max_distance) = synthetic_report();
+    assert_eq!(part_2(&input), Ok(max_distance));
+}
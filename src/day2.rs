@@ -1,73 +1,359 @@
-use std::str;
+//! Day 2: Dive! Follows the commands of the submarine and multiplies the
+//! final position and depth. Next to the commands of the puzzle, there is an
+//! extended command language, see [`Command`], and every state on the way can
+//! be followed with [`trajectory`]. Commands that take the submarine above the
+//! surface are errors in the puzzle, see [`Policy`] for the alternatives.
+//!
+//! ```
+//! use advent_of_code_2021::day2::{self, Direction, State, Steering};
+//!
+//! let input = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+//! assert_eq!(day2::parse(input).unwrap()[0], (Direction::Forward, 5));
+//! assert_eq!(day2::part_1(input), Ok(150));
+//! assert_eq!(day2::part_2(input), Ok(900));
+//!
+//! let commands = day2::parse_commands("down 2\nforward 3\nturn left\nback 1").unwrap();
+//! let states: Vec<State> = day2::trajectory(&commands, Steering::Aim).collect();
+//! assert_eq!(
+//!     states.last(),
+//!     Some(&State { horizontal: 4, depth: 4, aim: 2 })
+//! );
+//! ```
 
-enum Direction {
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Direction {
     Forward,
     Up,
     Down,
 }
 
-pub fn part_1(input: &str) -> u64 {
-    let (horizontal, depth) = parse(input).fold(
-        (0, 0),
-        |(horizontal, depth), (direction, magnitude)| match direction {
-            Direction::Down => (horizontal, depth + magnitude),
-            Direction::Forward => (horizontal + magnitude, depth),
-            Direction::Up => (horizontal, depth - magnitude),
-        },
-    );
-    horizontal * depth
+/// A command in the extended command language. Next to the commands of the
+/// puzzle, the submarine can move back, turn around, surface and set its aim
+/// directly. Distances are never negative.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Command {
+    /// `forward X`
+    Forward(i64),
+    /// `back X`, which moves like `forward` in the opposite direction.
+    Back(i64),
+    /// `down X`
+    Down(i64),
+    /// `up X`
+    Up(i64),
+    /// `turn left`
+    TurnLeft,
+    /// `turn right`
+    TurnRight,
+    /// `surface`, which brings the submarine straight up to a depth of 0.
+    Surface,
+    /// `aim X`, which sets the aim to `X`. It may be negative.
+    Aim(i64),
 }
 
-pub fn part_2(input: &str) -> u64 {
-    let (horizontal, depth, _aim) = parse(input).fold(
-        (0, 0, 0),
-        |(horizontal, depth, aim), (direction, magnitude)| match direction {
-            Direction::Down => (horizontal, depth, aim + magnitude),
-            Direction::Up => (horizontal, depth, aim - magnitude),
-            Direction::Forward => (horizontal + magnitude, depth + aim * magnitude, aim),
-        },
-    );
-    horizontal * depth
+impl From<(Direction, i64)> for Command {
+    fn from((direction, magnitude): (Direction, i64)) -> Self {
+        match direction {
+            Direction::Forward => Command::Forward(magnitude),
+            Direction::Down => Command::Down(magnitude),
+            Direction::Up => Command::Up(magnitude),
+        }
+    }
+}
+
+/// How `down` and `up` steer the submarine.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Steering {
+    /// They change the depth directly, like in part 1. The aim is only changed
+    /// by `aim`, and does not affect the depth.
+    Depth,
+    /// They change the aim, which changes the depth when moving, like in
+    /// part 2.
+    Aim,
+}
+
+/// What to do when a command takes the submarine above the surface. A negative
+/// aim only points the submarine up, and is allowed with every policy.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Policy {
+    /// Keep going with the negative depth.
+    Allow,
+    /// Keep the depth at zero instead.
+    Clamp,
+    /// Stop with an error for the command.
+    Error,
+}
+
+/// The position and aim of the submarine.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct State {
+    pub horizontal: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day2Error {
+    #[error("{0}: expected forward, down or up")]
+    UnknownDirection(Position),
+    #[error("{0}: expected a number")]
+    ExpectedNumber(Position),
+    #[error("{0}: expected forward, back, down, up, turn, surface or aim")]
+    UnknownCommand(Position),
+    #[error("{0}: expected left or right")]
+    ExpectedTurn(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: the command takes the submarine above the surface")]
+    AboveSurface(Position),
+    #[error("{0}: the command makes the position, depth or aim overflow")]
+    Overflow(Position),
+    #[error("the product of the position and depth overflows")]
+    AnswerOverflow,
+}
+
+/// Follow the puzzle commands, and multiply the final horizontal position and
+/// depth. It is an error for the submarine to go above the surface.
+fn final_product(input: &str, steering: Steering) -> Result<i64, Day2Error> {
+    let commands: Vec<Command> = parse(input)?.into_iter().map(Command::from).collect();
+    let state = checked_trajectory(&commands, steering, Policy::Error)
+        .try_fold(State::default(), |_, state| state)?;
+    state
+        .horizontal
+        .checked_mul(state.depth)
+        .ok_or(Day2Error::AnswerOverflow)
+}
+
+pub fn part_1(input: &str) -> Result<i64, Day2Error> {
+    final_product(input, Steering::Depth)
+}
+
+pub fn part_2(input: &str) -> Result<i64, Day2Error> {
+    final_product(input, Steering::Aim)
 }
 
 fn parse_direction(input: &mut &[u8]) -> Option<Direction> {
-    input.first().copied().map(|b| match b {
-        b'f' => {
-            *input = &input[8..];
-            Direction::Forward
-        }
-        b'd' => {
-            *input = &input[5..];
-            Direction::Down
-        }
-        b'u' => {
-            *input = &input[3..];
-            Direction::Up
-        }
-        _ => unreachable!("Invalid input, expected direction"),
-    })
+    let (direction, word): (_, &[u8]) = match input.first()? {
+        b'f' => (Direction::Forward, b"forward "),
+        b'd' => (Direction::Down, b"down "),
+        b'u' => (Direction::Up, b"up "),
+        _ => return None,
+    };
+    if input.starts_with(word) {
+        *input = &input[word.len()..];
+        Some(direction)
+    } else {
+        None
+    }
 }
 
-fn parse_number(input: &mut &[u8]) -> u64 {
-    const NUMBER_MASK: u8 = 0b0100000;
-    std::iter::from_fn(|| {
-        // Take the first digit while
-        input.split_first().map(|(digit, remainder)| {
-            *input = remainder;
-            digit
+pub fn parse(input: &str) -> Result<Vec<(Direction, i64)>, Day2Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let mut remainder = bytes;
+    let mut commands = Vec::with_capacity(bytes.len() / 8);
+    while !remainder.is_empty() {
+        let direction = parse_direction(&mut remainder)
+            .ok_or_else(|| Day2Error::UnknownDirection(Position::of_remainder(bytes, remainder)))?;
+        let magnitude = parse_signed_number(&mut remainder, false)
+            .ok_or_else(|| Day2Error::ExpectedNumber(Position::of_remainder(bytes, remainder)))?;
+        commands.push((direction, magnitude));
+    }
+    Ok(commands)
+}
+
+/// Follow the commands from the surface, and yield the state after every
+/// command. The submarine moves along a single horizontal line, so turning
+/// either way turns it around: after an odd number of turns `forward` moves to
+/// lower horizontal positions. The aim is relative to the submarine, so it
+/// still makes the submarine go deeper when moving forward after turning.
+///
+/// The trajectory ends before a command that makes a value overflow, use
+/// [`checked_trajectory`] to find out about it.
+pub fn trajectory(commands: &[Command], steering: Steering) -> impl Iterator<Item = State> + '_ {
+    checked_trajectory(commands, steering, Policy::Allow).map_while(Result::ok)
+}
+
+/// Follow the commands like [`trajectory`], and apply the policy whenever a
+/// command takes the submarine above the surface.
+/// With [`Policy::Error`], the error for the first such command is yielded
+/// last. Its position is the line of the command, as there is a command on
+/// every line. A command that makes a value overflow is an error with every
+/// policy.
+pub fn checked_trajectory(
+    commands: &[Command],
+    steering: Steering,
+    policy: Policy,
+) -> impl Iterator<Item = Result<State, Day2Error>> + '_ {
+    let mut heading = 1;
+    let mut state = State::default();
+    commands
+        .iter()
+        .enumerate()
+        .map(move |(i, command)| {
+            let position = Position {
+                line: i + 1,
+                column: 1,
+            };
+            let advance = move |state: State, distance: i64| {
+                Some(State {
+                    horizontal: state.horizontal.checked_add(heading * distance)?,
+                    depth: match steering {
+                        Steering::Depth => state.depth,
+                        Steering::Aim => {
+                            state.depth.checked_add(state.aim.checked_mul(distance)?)?
+                        }
+                    },
+                    ..state
+                })
+            };
+            let next = match (*command, steering) {
+                (Command::Forward(distance), _) => advance(state, distance),
+                (Command::Back(distance), _) => advance(state, -distance),
+                (Command::Down(x), Steering::Depth) => state
+                    .depth
+                    .checked_add(x)
+                    .map(|depth| State { depth, ..state }),
+                (Command::Up(x), Steering::Depth) => state
+                    .depth
+                    .checked_sub(x)
+                    .map(|depth| State { depth, ..state }),
+                (Command::Down(x), Steering::Aim) => {
+                    state.aim.checked_add(x).map(|aim| State { aim, ..state })
+                }
+                (Command::Up(x), Steering::Aim) => {
+                    state.aim.checked_sub(x).map(|aim| State { aim, ..state })
+                }
+                (Command::TurnLeft | Command::TurnRight, _) => {
+                    heading = -heading;
+                    Some(state)
+                }
+                (Command::Surface, _) => Some(State { depth: 0, ..state }),
+                (Command::Aim(aim), _) => Some(State { aim, ..state }),
+            };
+            state = next.ok_or(Day2Error::Overflow(position))?;
+            match policy {
+                Policy::Allow => {}
+                Policy::Clamp => state.depth = state.depth.max(0),
+                Policy::Error if state.depth < 0 => return Err(Day2Error::AboveSurface(position)),
+                Policy::Error => {}
+            }
+            Ok(state)
+        })
+        .scan(false, |failed, state| {
+            // Stop after the first error
+            (!std::mem::replace(failed, state.is_err())).then_some(state)
         })
-    })
-    .take_while(|d| *d & NUMBER_MASK != 0)
-    .inspect(|d| debug_assert!(d.is_ascii_digit()))
-    .map(|d| u64::from(d - b'0'))
-    .fold(0, |acc, d| 10 * acc + d)
 }
 
-fn parse(input: &str) -> impl Iterator<Item = (Direction, u64)> + '_ {
-    let mut input = input.as_bytes();
-    std::iter::from_fn(move || {
-        parse_direction(&mut input).map(|direction| (direction, parse_number(&mut input)))
-    })
+/// Check that none of the commands take the submarine above the surface, and
+/// return the error for the first one that does.
+pub fn validate(commands: &[Command], steering: Steering) -> Result<(), Day2Error> {
+    checked_trajectory(commands, steering, Policy::Error).try_for_each(|state| state.map(drop))
+}
+
+/// Remove `word` from the start of the input, if it is there.
+fn strip_word(input: &mut &[u8], word: &[u8]) -> bool {
+    if let Some(rest) = input.strip_prefix(word) {
+        *input = rest;
+        true
+    } else {
+        false
+    }
+}
+
+/// Parse a number that fits in an `i64`, optionally with a minus sign, followed
+/// by a newline or the end of the input.
+fn parse_signed_number(input: &mut &[u8], allow_negative: bool) -> Option<i64> {
+    let negative = allow_negative && strip_word(input, b"-");
+    let digits = input.iter().take_while(|d| d.is_ascii_digit()).count();
+    if digits == 0 || !matches!(input.get(digits), None | Some(b'\n')) {
+        return None;
+    }
+    let number = input[..digits].iter().try_fold(0i64, |acc, d| {
+        acc.checked_mul(10)?.checked_add(i64::from(d - b'0'))
+    })?;
+    *input = &input[(digits + 1).min(input.len())..];
+    Some(if negative { -number } else { number })
+}
+
+/// Parse the commands in the extended command language.
+pub fn parse_commands(input: &str) -> Result<Vec<Command>, Day2Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let error_at = |remainder: &[u8]| Position::of_remainder(bytes, remainder);
+    let mut remainder = bytes;
+    let mut commands = Vec::with_capacity(bytes.len() / 8);
+    while !remainder.is_empty() {
+        let start = remainder;
+        let distance: Option<fn(i64) -> Command> = if strip_word(&mut remainder, b"forward ") {
+            Some(Command::Forward)
+        } else if strip_word(&mut remainder, b"back ") {
+            Some(Command::Back)
+        } else if strip_word(&mut remainder, b"down ") {
+            Some(Command::Down)
+        } else if strip_word(&mut remainder, b"up ") {
+            Some(Command::Up)
+        } else {
+            None
+        };
+        let command = if let Some(command) = distance {
+            command(
+                parse_signed_number(&mut remainder, false)
+                    .ok_or_else(|| Day2Error::ExpectedNumber(error_at(remainder)))?,
+            )
+        } else if strip_word(&mut remainder, b"aim ") {
+            Command::Aim(
+                parse_signed_number(&mut remainder, true)
+                    .ok_or_else(|| Day2Error::ExpectedNumber(error_at(remainder)))?,
+            )
+        } else {
+            let command = if strip_word(&mut remainder, b"turn ") {
+                if strip_word(&mut remainder, b"left") {
+                    Command::TurnLeft
+                } else if strip_word(&mut remainder, b"right") {
+                    Command::TurnRight
+                } else {
+                    return Err(Day2Error::ExpectedTurn(error_at(remainder)));
+                }
+            } else if strip_word(&mut remainder, b"surface") {
+                Command::Surface
+            } else {
+                return Err(Day2Error::UnknownCommand(error_at(start)));
+            };
+            match remainder {
+                [] => {}
+                [b'\n', rest @ ..] => remainder = rest,
+                _ => return Err(Day2Error::ExpectedNewline(error_at(remainder))),
+            }
+            command
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("forward 5\nbackward 3"),
+        Err(Day2Error::UnknownDirection(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse("forward 5\nfor 3"),
+        Err(Day2Error::UnknownDirection(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse("forward 5\ndown x"),
+        Err(Day2Error::ExpectedNumber(Position { line: 2, column: 6 }))
+    );
+    assert_eq!(
+        parse("up 3a"),
+        Err(Day2Error::ExpectedNumber(Position { line: 1, column: 4 }))
+    );
 }
 
 #[test]
@@ -78,7 +364,7 @@ forward 8
 up 3
 down 8
 forward 2";
-    assert_eq!(part_1(input), 150);
+    assert_eq!(part_1(input), Ok(150));
 }
 
 #[test]
@@ -89,17 +375,167 @@ forward 8
 up 3
 down 8
 forward 2";
-    assert_eq!(part_2(input), 900);
+    assert_eq!(part_2(input), Ok(900));
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day2.txt");
-    assert_eq!(part_1(input), 1813801);
+fn test_parse_commands() {
+    assert_eq!(
+        parse_commands("forward 5\nback 2\nturn left\nturn right\nsurface\naim -3\nup 1\n"),
+        Ok(vec![
+            Command::Forward(5),
+            Command::Back(2),
+            Command::TurnLeft,
+            Command::TurnRight,
+            Command::Surface,
+            Command::Aim(-3),
+            Command::Up(1),
+        ])
+    );
+    assert_eq!(
+        parse_commands("forward 5\nsideways 3"),
+        Err(Day2Error::UnknownCommand(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse_commands("turn around"),
+        Err(Day2Error::ExpectedTurn(Position { line: 1, column: 6 }))
+    );
+    assert_eq!(
+        parse_commands("surface 3"),
+        Err(Day2Error::ExpectedNewline(Position { line: 1, column: 8 }))
+    );
+    assert_eq!(
+        parse_commands("back -3"),
+        Err(Day2Error::ExpectedNumber(Position { line: 1, column: 6 }))
+    );
+    assert_eq!(
+        parse_commands("aim 99999999999999999999"),
+        Err(Day2Error::ExpectedNumber(Position { line: 1, column: 5 }))
+    );
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day2.txt");
-    assert_eq!(part_2(input), 1960569556);
+fn test_trajectory() {
+    let input = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2";
+    let commands = parse_commands(input).unwrap();
+    let last = |steering| trajectory(&commands, steering).last().unwrap();
+    assert_eq!(
+        last(Steering::Depth),
+        State {
+            horizontal: 15,
+            depth: 10,
+            aim: 0
+        }
+    );
+    assert_eq!(
+        last(Steering::Aim),
+        State {
+            horizontal: 15,
+            depth: 60,
+            aim: 10
+        }
+    );
+    assert!(trajectory(&commands, Steering::Aim).all(|state| state.depth >= 0));
+
+    let commands =
+        parse_commands("aim 2\nforward 3\nturn right\nforward 1\nback 4\nsurface").unwrap();
+    let states: Vec<_> = trajectory(&commands, Steering::Aim)
+        .map(|s| (s.horizontal, s.depth, s.aim))
+        .collect();
+    assert_eq!(
+        states,
+        [
+            (0, 0, 2),
+            (3, 6, 2),
+            (3, 6, 2),
+            (2, 8, 2),
+            (6, 0, 2),
+            (6, 0, 2)
+        ]
+    );
+}
+
+#[test]
+fn test_policy() {
+    assert_eq!(
+        part_1("forward 5\ndown 2\nup 3\nforward 1"),
+        Err(Day2Error::AboveSurface(Position { line: 3, column: 1 }))
+    );
+    assert_eq!(part_2("down 2\nforward 3\nup 3\nforward 1"), Ok(20));
+    assert_eq!(
+        part_2("down 1\nforward 2\nup 3\nforward 1\nforward 1"),
+        Err(Day2Error::AboveSurface(Position { line: 5, column: 1 }))
+    );
+
+    let commands = parse_commands("down 1\nup 3\nforward 2\naim -1\nforward 1").unwrap();
+    let depths = |steering, policy| {
+        checked_trajectory(&commands, steering, policy)
+            .map(|state| state.map(|state| (state.depth, state.aim)))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        depths(Steering::Depth, Policy::Allow),
+        [
+            Ok((1, 0)),
+            Ok((-2, 0)),
+            Ok((-2, 0)),
+            Ok((-2, -1)),
+            Ok((-2, -1))
+        ]
+    );
+    assert_eq!(
+        depths(Steering::Depth, Policy::Clamp),
+        [Ok((1, 0)), Ok((0, 0)), Ok((0, 0)), Ok((0, -1)), Ok((0, -1))]
+    );
+    assert_eq!(
+        depths(Steering::Aim, Policy::Allow),
+        [
+            Ok((0, 1)),
+            Ok((0, -2)),
+            Ok((-4, -2)),
+            Ok((-4, -1)),
+            Ok((-5, -1))
+        ]
+    );
+    assert_eq!(
+        depths(Steering::Aim, Policy::Clamp),
+        [
+            Ok((0, 1)),
+            Ok((0, -2)),
+            Ok((0, -2)),
+            Ok((0, -1)),
+            Ok((0, -1))
+        ]
+    );
+    assert_eq!(
+        depths(Steering::Aim, Policy::Error),
+        [
+            Ok((0, 1)),
+            Ok((0, -2)),
+            Err(Day2Error::AboveSurface(Position { line: 3, column: 1 }))
+        ]
+    );
+    assert_eq!(
+        validate(&commands, Steering::Depth),
+        Err(Day2Error::AboveSurface(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(validate(&commands[..1], Steering::Aim), Ok(()));
+}
+
+#[test]
+fn test_overflow() {
+    assert_eq!(
+        part_2("down 4294967296\nforward 4294967296"),
+        Err(Day2Error::Overflow(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        part_1("forward 4294967296\ndown 4294967296"),
+        Err(Day2Error::AnswerOverflow)
+    );
+    let commands = parse_commands("forward 9223372036854775807\nturn left\nback 1\nup 1").unwrap();
+    assert_eq!(
+        checked_trajectory(&commands, Steering::Depth, Policy::Allow).last(),
+        Some(Err(Day2Error::Overflow(Position { line: 3, column: 1 })))
+    );
+    assert_eq!(trajectory(&commands, Steering::Depth).count(), 2);
 }
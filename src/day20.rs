@@ -0,0 +1,327 @@
+//! Day 20: Trench Map. Enhances the image with the image enhancement
+//! algorithm.
+//!
+//! ```
+//! use advent_of_code_2021::day20;
+//!
+//! // Every pixel keeps its value
+//! let algorithm: String = (0..512)
+//!     .map(|i| if i & 0b1_0000 != 0 { '#' } else { '.' })
+//!     .collect();
+//! let input = format!("{}\n\n#..\n..#", algorithm);
+//! assert_eq!(day20::part_1(&input), Ok(2));
+//! assert_eq!(day20::lit_after_enhancing(&input, 5), Ok(2));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+const ALGORITHM_LEN: usize = 512;
+const WORD_BITS: usize = 64;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day20Error {
+    #[error("{0}: expected '#' or '.'")]
+    ExpectedPixel(Position),
+    #[error("the algorithm has {0} entries instead of {}", ALGORITHM_LEN)]
+    AlgorithmLength(usize),
+    #[error("{0}: expected an empty line")]
+    ExpectedEmptyLine(Position),
+    #[error("{0}: image rows differ in width")]
+    UnequalWidth(Position),
+    #[error("infinitely many pixels are lit")]
+    InfinitelyManyLit,
+}
+
+/// Whether the pixel is lit, or `None` if the byte is not a pixel.
+fn pixel(b: u8) -> Option<bool> {
+    match b {
+        b'#' => Some(true),
+        b'.' => Some(false),
+        _ => None,
+    }
+}
+
+/// The image enhancement algorithm, one bit per entry.
+struct Algorithm([u64; ALGORITHM_LEN / WORD_BITS]);
+
+impl Algorithm {
+    /// Parse the algorithm, which is the first line of the input.
+    fn parse(line: &[u8]) -> Result<Self, Day20Error> {
+        if line.len() != ALGORITHM_LEN {
+            return Err(Day20Error::AlgorithmLength(line.len()));
+        }
+        let mut bits = [0; ALGORITHM_LEN / WORD_BITS];
+        for (i, b) in line.iter().enumerate() {
+            let lit = pixel(*b).ok_or(Day20Error::ExpectedPixel(Position {
+                line: 1,
+                column: i + 1,
+            }))?;
+            bits[i / WORD_BITS] |= u64::from(lit) << (i % WORD_BITS);
+        }
+        Ok(Algorithm(bits))
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.0[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+}
+
+/// A bit-packed image. Every row is stored in `words_per_row` words, pixel `x`
+/// being bit `x % 64` of word `x / 64`, so that the image can be enhanced a
+/// word at a time. Everything outside of the image has the value of
+/// `background`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Image {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+    background: bool,
+}
+
+impl Image {
+    /// Parse the image and surround it with `margin` background pixels on
+    /// every side, so that it can be enhanced `margin - 1` times without
+    /// growing beyond its borders. The image starts at line `first_line` of
+    /// the input, for error reporting.
+    fn parse(input: &[u8], margin: usize, first_line: usize) -> Result<Self, Day20Error> {
+        let input_width = memchr::memchr(b'\n', input).unwrap_or(input.len());
+        let input_height = input
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .count();
+        let mut image = Image::empty(input_width + 2 * margin, input_height + 2 * margin, false);
+        for (y, line) in input.split(|b| *b == b'\n').take(input_height).enumerate() {
+            let error_at = |x: usize| Position {
+                line: first_line + y,
+                column: x + 1,
+            };
+            if line.len() != input_width {
+                return Err(Day20Error::UnequalWidth(error_at(
+                    line.len().min(input_width),
+                )));
+            }
+            for (x, b) in line.iter().enumerate() {
+                let lit = pixel(*b).ok_or_else(|| Day20Error::ExpectedPixel(error_at(x)))?;
+                image.set(x + margin, y + margin, lit);
+            }
+        }
+        Ok(image)
+    }
+
+    fn empty(width: usize, height: usize, background: bool) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Image {
+            width,
+            height,
+            words_per_row,
+            bits: vec![0; words_per_row * height],
+            background,
+        }
+    }
+
+    #[cfg(test)]
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.bits[y * self.words_per_row + x / WORD_BITS] & (1 << (x % WORD_BITS)) != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        self.bits[y * self.words_per_row + x / WORD_BITS] |= u64::from(value) << (x % WORD_BITS);
+    }
+
+    /// The bits of word `w` of a row that lie beyond the width of the image.
+    fn padding(&self, w: usize) -> u64 {
+        u64::MAX
+            .checked_shl((self.width - w * WORD_BITS) as u32)
+            .unwrap_or(0)
+    }
+
+    /// Word `w` of row `y`, in which the pixels outside of the image have the
+    /// value of the background. The stored padding bits are always zero.
+    fn word(&self, w: isize, y: isize) -> u64 {
+        let fill = if self.background { u64::MAX } else { 0 };
+        if w < 0 || y < 0 || w as usize >= self.words_per_row || y as usize >= self.height {
+            fill
+        } else {
+            let (w, y) = (w as usize, y as usize);
+            self.bits[y * self.words_per_row + w] | (fill & self.padding(w))
+        }
+    }
+
+    fn enhance(&self, algorithm: &Algorithm) -> Self {
+        // An infinite area of background pixels maps to index 0 or 511
+        let background = algorithm.get(if self.background {
+            ALGORITHM_LEN - 1
+        } else {
+            0
+        });
+        let mut enhanced = Image::empty(self.width, self.height, background);
+        for y in 0..self.height as isize {
+            for w in 0..self.words_per_row as isize {
+                // The nine bits of the index of every pixel in this word, from
+                // the most significant one: the words of the rows above, at
+                // and below `y`, shifted so that bit `i` holds the pixel to the
+                // left of, at and to the right of pixel `i`.
+                let mut planes = [0; 9];
+                for (row, dy) in [-1, 0, 1].into_iter().enumerate() {
+                    let previous = self.word(w - 1, y + dy);
+                    let current = self.word(w, y + dy);
+                    let next = self.word(w + 1, y + dy);
+                    planes[3 * row] = current << 1 | previous >> (WORD_BITS - 1);
+                    planes[3 * row + 1] = current;
+                    planes[3 * row + 2] = current >> 1 | next << (WORD_BITS - 1);
+                }
+                let mut enhanced_word = 0;
+                for i in 0..WORD_BITS {
+                    let index = planes
+                        .iter()
+                        .fold(0, |index, plane| index << 1 | (plane >> i & 1) as usize);
+                    enhanced_word |= u64::from(algorithm.get(index)) << i;
+                }
+                let (w, y) = (w as usize, y as usize);
+                enhanced.bits[y * self.words_per_row + w] = enhanced_word & !self.padding(w);
+            }
+        }
+        enhanced
+    }
+
+    /// The number of lit pixels, or an error if infinitely many are lit.
+    fn lit_pixels(&self) -> Result<usize, Day20Error> {
+        if self.background {
+            Err(Day20Error::InfinitelyManyLit)
+        } else {
+            Ok(self.bits.iter().map(|w| w.count_ones() as usize).sum())
+        }
+    }
+}
+
+/// Parse the algorithm and the image, with room to enhance it `rounds` times.
+fn parse(input: &str, rounds: usize) -> Result<(Algorithm, Image), Day20Error> {
+    let input = normalize::line_endings(input);
+    let input = input.as_bytes();
+    let algorithm_end = memchr::memchr(b'\n', input).unwrap_or(input.len());
+    let algorithm = Algorithm::parse(&input[..algorithm_end])?;
+    if input.get(algorithm_end + 1) != Some(&b'\n') {
+        return Err(Day20Error::ExpectedEmptyLine(Position {
+            line: 2,
+            column: 1,
+        }));
+    }
+    let image = Image::parse(&input[algorithm_end + 2..], rounds + 1, 3)?;
+    Ok((algorithm, image))
+}
+
+pub fn lit_after_enhancing(input: &str, rounds: usize) -> Result<usize, Day20Error> {
+    let (algorithm, mut image) = parse(input, rounds)?;
+    for _ in 0..rounds {
+        image = image.enhance(&algorithm);
+    }
+    image.lit_pixels()
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day20Error> {
+    lit_after_enhancing(input, 2)
+}
+
+pub fn part_2(input: &str) -> Result<usize, Day20Error> {
+    lit_after_enhancing(input, 50)
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##\
+#..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###\
+.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#.\
+.#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#.....\
+.#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#..\
+...####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.....\
+..##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###";
+
+#[test]
+fn test_algorithm_parse() {
+    let algorithm = Algorithm::parse(EXAMPLE.lines().next().unwrap().as_bytes()).unwrap();
+    assert!(!algorithm.get(0));
+    assert!(algorithm.get(2));
+    assert!(algorithm.get(34));
+    assert!(algorithm.get(ALGORITHM_LEN - 1));
+}
+
+#[test]
+fn test_flipping_background() {
+    // Everything dark becomes lit and everything lit becomes dark
+    let mut algorithm = "#".repeat(ALGORITHM_LEN - 1);
+    algorithm.push('.');
+    let input = format!("{}\n\n...\n.#.\n...", algorithm);
+    assert_eq!(
+        lit_after_enhancing(&input, 1),
+        Err(Day20Error::InfinitelyManyLit)
+    );
+    // After the first step every pixel is lit, so every pixel goes dark again
+    assert_eq!(lit_after_enhancing(&input, 2), Ok(0));
+}
+
+#[test]
+fn test_enhance_across_words() {
+    // Every pixel takes the value of its left neighbour, so the lit pixel
+    // moves to the right, from one word into the next
+    let algorithm: String = (0..ALGORITHM_LEN)
+        .map(|i| if i & 0b10_0000 != 0 { '#' } else { '.' })
+        .collect();
+    let algorithm = Algorithm::parse(algorithm.as_bytes()).unwrap();
+    let mut row = ".".repeat(70);
+    row.replace_range(62..63, "#");
+    let mut image = Image::parse(row.as_bytes(), 0, 1).unwrap();
+    for x in 63..70 {
+        image = image.enhance(&algorithm);
+        assert!(image.get(x, 0));
+        assert_eq!(image.lit_pixels(), Ok(1));
+    }
+    // The pixel leaves the image, without setting the padding bits
+    image = image.enhance(&algorithm);
+    assert_eq!(image.lit_pixels(), Ok(0));
+}
+
+#[test]
+fn test_parse_error() {
+    let algorithm = EXAMPLE.lines().next().unwrap();
+    assert_eq!(
+        part_1(&format!("{}\n\n#..\n#x.", algorithm)),
+        Err(Day20Error::ExpectedPixel(Position { line: 4, column: 2 }))
+    );
+    assert_eq!(
+        part_1(&format!("{}\n\n#..\n#.", algorithm)),
+        Err(Day20Error::UnequalWidth(Position { line: 4, column: 3 }))
+    );
+    assert_eq!(
+        part_1(&format!("{}\n#..\n#..", algorithm)),
+        Err(Day20Error::ExpectedEmptyLine(Position {
+            line: 2,
+            column: 1
+        }))
+    );
+    assert_eq!(
+        part_1(&format!("{}x\n\n#..", &algorithm[1..])),
+        Err(Day20Error::ExpectedPixel(Position {
+            line: 1,
+            column: ALGORITHM_LEN
+        }))
+    );
+    assert_eq!(part_1("#.#\n\n#.."), Err(Day20Error::AlgorithmLength(3)));
+}
+
+#[test]
+fn test_part_1_example() {
+    assert_eq!(part_1(EXAMPLE), Ok(35));
+}
+
+#[test]
+fn test_part_2_example() {
+    assert_eq!(part_2(EXAMPLE), Ok(3351));
+}
@@ -0,0 +1,242 @@
+//! The computation of the answers for every pair of starting positions. This
+//! file is also included by the build script, which writes the answers to the
+//! tables in `day21.rs`.
+
+struct Dice(u8, usize);
+
+impl Dice {
+    fn new() -> Self {
+        Dice(6, 0)
+    }
+
+    fn next_sum_mod_10(&mut self) -> u8 {
+        let curr = self.0;
+        // We do this mod 10 anyway, so do so immediately
+        self.0 += 3 * 3;
+        self.1 += 3;
+        if self.0 >= 10 {
+            self.0 -= 10;
+        }
+        curr
+    }
+
+    fn times_trown(&self) -> usize {
+        self.1
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+struct Player {
+    current_pos: u8,
+    score: usize,
+}
+
+impl Player {
+    fn new(current_pos: u8) -> Self {
+        Player {
+            current_pos,
+            score: 0,
+        }
+    }
+
+    fn next_turn(&mut self, dice: &mut Dice) {
+        self.current_pos += dice.next_sum_mod_10();
+        if self.current_pos >= 10 {
+            self.current_pos -= 10;
+        }
+        self.score += self.current_pos as usize + 1;
+    }
+
+    fn has_won(&self) -> bool {
+        self.score >= 1000
+    }
+
+    fn score(&self) -> usize {
+        self.score
+    }
+}
+
+/// The key of the starting positions `a` and `b`, from 1 to 10. The tables are
+/// indexed by the ASCII digits of the positions modulo 10, as
+/// `(a ^ (b << 4))`.
+fn key(a: u8, b: u8) -> usize {
+    let digit = |position: u8| b'0' + position % 10;
+    usize::from(digit(a) ^ (digit(b) << 4))
+}
+
+/// The answers to part 1, stored by the key of the starting positions.
+pub fn final_scores() -> [usize; 256] {
+    let mut final_score = [0; 256];
+    let mut x = 1;
+    while x <= 10 {
+        let mut y = 1;
+        while y <= 10 {
+            let dice = &mut Dice::new();
+            let mut p_1 = Player::new(x - 1);
+            let mut p_2 = Player::new(y - 1);
+            final_score[key(x, y)] = loop {
+                p_1.next_turn(dice);
+                if p_1.has_won() {
+                    break p_2.score() * dice.times_trown();
+                }
+
+                p_2.next_turn(dice);
+                if p_2.has_won() {
+                    break p_1.score() * dice.times_trown();
+                }
+            };
+            y += 1;
+        }
+        x += 1;
+    }
+    final_score
+}
+
+const MIN_THREE_DICE_RESULT: usize = 3;
+const MAX_THREE_DICE_RESULT: usize = 9;
+
+/// An array representing the number of ways to throw that outcome.
+/// ```rust
+/// assert_eq!(THREE_DICE_OUTCOMES[3], 1); // Throw 1, 1, 1
+/// assert_eq!(THREE_DICE_OUTCOMES[9], 1); // Throw 3, 3, 3
+/// ```
+const THREE_DICE_OUTCOMES: [usize; 10] = {
+    let mut outcomes = [0; 10];
+    let mut a = 1;
+    while a <= 3 {
+        let mut b = 1;
+        while b <= 3 {
+            let mut c = 1;
+            while c <= 3 {
+                outcomes[a + b + c] += 1;
+                c += 1;
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+    outcomes
+};
+
+const WINNING_SCORE: usize = 21;
+const POSITIONS: usize = 10;
+
+#[derive(Default)]
+struct Table([[[[usize; POSITIONS]; WINNING_SCORE]; POSITIONS]; WINNING_SCORE]);
+
+impl Table {
+    fn empty() -> Self {
+        Table([[[[0; POSITIONS]; WINNING_SCORE]; POSITIONS]; WINNING_SCORE])
+    }
+
+    fn from_starting_position(own: usize, other: usize) -> Self {
+        let mut t = [[[[0; POSITIONS]; WINNING_SCORE]; POSITIONS]; WINNING_SCORE];
+        t[0][own][0][other] = 1;
+        Table(t)
+    }
+
+    fn do_turn(
+        &mut self,
+        own_score: usize,
+        other_score: usize,
+        own_position: usize,
+        other_position: usize,
+        other_player: &Table,
+    ) -> usize {
+        let mut possibilities = 0;
+        let mut dice = MIN_THREE_DICE_RESULT;
+        while dice <= MAX_THREE_DICE_RESULT {
+            // The current player position can be derived from their score.
+            let own_position_new = (own_position + dice) % POSITIONS;
+            let own_score_new = own_score + own_position_new + 1;
+            let frequency = THREE_DICE_OUTCOMES[dice];
+            if own_score_new >= WINNING_SCORE {
+                possibilities += other_player.0[other_score][other_position][own_score]
+                    [own_position]
+                    * frequency;
+            } else {
+                self.0[own_score_new][own_position_new][other_score][other_position] += other_player
+                    .0[other_score][other_position][own_score][own_position]
+                    * frequency;
+            }
+            dice += 1;
+        }
+        possibilities
+    }
+}
+
+fn compute_possibilities_to_reach_score(start_p1: usize, start_p2: usize) -> usize {
+    let mut after_turn_p2 = Table::from_starting_position(start_p2 - 1, start_p1 - 1);
+    let mut after_turn_p1 = Table::empty();
+    let mut possibilities_p1 = 0;
+    let mut possibilities_p2 = 0;
+    let mut p1_score = 0;
+    while p1_score < WINNING_SCORE {
+        let mut p2_score = 0;
+        while p2_score < WINNING_SCORE {
+            let mut p1_position = 0;
+            while p1_position < POSITIONS {
+                let mut p2_position = 0;
+                while p2_position < POSITIONS {
+                    possibilities_p1 += after_turn_p1.do_turn(
+                        p1_score,
+                        p2_score,
+                        p1_position,
+                        p2_position,
+                        &after_turn_p2,
+                    );
+                    possibilities_p2 += after_turn_p2.do_turn(
+                        p2_score,
+                        p1_score,
+                        p2_position,
+                        p1_position,
+                        &after_turn_p1,
+                    );
+                    p2_position += 1;
+                }
+                p1_position += 1;
+            }
+            p2_score += 1;
+        }
+        p1_score += 1;
+    }
+
+    if possibilities_p1 > possibilities_p2 {
+        possibilities_p1
+    } else {
+        possibilities_p2
+    }
+}
+
+/// The solutions for part 2, stored by the key of the starting positions.
+pub fn part_2_solutions() -> [usize; 256] {
+    let mut solutions = [0; 256];
+    let mut a = 1;
+    while a <= 10 {
+        let mut b = 1;
+        while b <= 10 {
+            solutions[key(a, b)] =
+                compute_possibilities_to_reach_score(usize::from(a), usize::from(b));
+            b += 1;
+        }
+        a += 1;
+    }
+    solutions
+}
+
+#[test]
+fn test_die() {
+    let mut dice_struct = Dice::new();
+    let mut dice = 1_usize;
+    for round in 0..1000 {
+        let sum = dice + dice + 1 + dice + 2;
+        dice += 3;
+        dice %= 1000;
+        assert_eq!(
+            usize::from(dice_struct.next_sum_mod_10()) % 10,
+            sum % 10,
+            "failure in round {}",
+            round
+        );
+    }
+}
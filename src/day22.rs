@@ -0,0 +1,379 @@
+//! Day 22: Reactor Reboot. Counts the cubes that are on after the reboot
+//! steps.
+//!
+//! ```
+//! use advent_of_code_2021::day22;
+//!
+//! let input = "on x=10..12,y=10..12,z=10..12\non x=11..13,y=11..13,z=11..13
+//! off x=9..11,y=9..11,z=9..11\non x=10..10,y=10..10,z=10..10";
+//! assert_eq!(day22::part_1(input), Ok(39));
+//! assert_eq!(day22::part_2(input), Ok(39));
+//! ```
+
+use std::collections::HashMap;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::i64 as number,
+    combinator::{all_consuming, map, value},
+    sequence::{preceded, separated_pair, tuple},
+    IResult,
+};
+#[cfg(test)]
+use rand::{Rng, SeedableRng};
+#[cfg(test)]
+use rand_chacha::ChaCha8Rng;
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day22Error {
+    #[error("{0}: expected 'on' or 'off' followed by a cuboid")]
+    Parse(Position),
+    #[error("line {0}: the cuboid is empty")]
+    EmptyCuboid(usize),
+    #[error("the number of cubes that are on overflows")]
+    Overflow,
+}
+
+/// A cuboid of cubes, with inclusive bounds along every axis.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Cuboid {
+    pub min: [i64; 3],
+    pub max: [i64; 3],
+}
+
+impl Cuboid {
+    /// The number of cubes in the cuboid, or `None` if it does not fit in an
+    /// `i128`. The side lengths always do.
+    pub fn volume(&self) -> Option<i128> {
+        (0..3)
+            .map(|axis| i128::from(self.max[axis]) - i128::from(self.min[axis]) + 1)
+            .try_fold(1, i128::checked_mul)
+    }
+
+    pub fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let mut intersection = *self;
+        for axis in 0..3 {
+            intersection.min[axis] = self.min[axis].max(other.min[axis]);
+            intersection.max[axis] = self.max[axis].min(other.max[axis]);
+            if intersection.min[axis] > intersection.max[axis] {
+                return None;
+            }
+        }
+        Some(intersection)
+    }
+
+    fn parse(input: &str) -> IResult<&str, Self> {
+        let range = |axis| preceded(tag(axis), separated_pair(number, tag(".."), number));
+        map(
+            tuple((range("x="), tag(","), range("y="), tag(","), range("z="))),
+            |((x_min, x_max), _, (y_min, y_max), _, (z_min, z_max))| Cuboid {
+                min: [x_min, y_min, z_min],
+                max: [x_max, y_max, z_max],
+            },
+        )(input)
+    }
+}
+
+/// A reboot step, turning all cubes in the cuboid on or off.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Step {
+    pub on: bool,
+    pub cuboid: Cuboid,
+}
+
+pub fn parse(input: &str) -> Result<Vec<Step>, Day22Error> {
+    normalize::line_endings(input)
+        .split('\n')
+        .enumerate()
+        .map(|(y, line)| {
+            let step = all_consuming(map(
+                separated_pair(
+                    alt((value(true, tag("on")), value(false, tag("off")))),
+                    tag(" "),
+                    Cuboid::parse,
+                ),
+                |(on, cuboid)| Step { on, cuboid },
+            ))(line)
+            .map_err(|e| {
+                Day22Error::Parse(Position {
+                    line: y + 1,
+                    column: Position::of_nom_error(line.as_bytes(), &e).column,
+                })
+            })?
+            .1;
+            if (0..3).any(|axis| step.cuboid.min[axis] > step.cuboid.max[axis]) {
+                return Err(Day22Error::EmptyCuboid(y + 1));
+            }
+            Ok(step)
+        })
+        .collect()
+}
+
+/// Count the cubes that are on after the reboot steps, using
+/// inclusion–exclusion: every step cancels out its overlap with the cuboids
+/// counted so far, and an "on" step then adds its own cuboid.
+fn cubes_on(steps: impl Iterator<Item = Step>) -> Result<i64, Day22Error> {
+    // The multiplicity of every signed cuboid
+    let mut cuboids: HashMap<Cuboid, i64> = HashMap::new();
+    for Step { on, cuboid } in steps {
+        let mut updates: HashMap<Cuboid, i64> = HashMap::new();
+        for (other, sign) in cuboids.iter() {
+            if let Some(intersection) = cuboid.intersection(other) {
+                *updates.entry(intersection).or_default() -= sign;
+            }
+        }
+        if on {
+            *updates.entry(cuboid).or_default() += 1;
+        }
+        for (cuboid, sign) in updates {
+            *cuboids.entry(cuboid).or_default() += sign;
+        }
+        cuboids.retain(|_, sign| *sign != 0);
+    }
+    // The terms may be far larger than the total
+    let total = cuboids
+        .into_iter()
+        .try_fold(0_i128, |total, (cuboid, sign)| {
+            cuboid
+                .volume()?
+                .checked_mul(i128::from(sign))?
+                .checked_add(total)
+        });
+    total
+        .and_then(|total| i64::try_from(total).ok())
+        .ok_or(Day22Error::Overflow)
+}
+
+const INITIALIZATION_REGION: Cuboid = Cuboid {
+    min: [-50; 3],
+    max: [50; 3],
+};
+
+pub fn part_1(input: &str) -> Result<i64, Day22Error> {
+    cubes_on(parse(input)?.into_iter().filter_map(|step| {
+        step.cuboid
+            .intersection(&INITIALIZATION_REGION)
+            .map(|cuboid| Step { cuboid, ..step })
+    }))
+}
+
+pub fn part_2(input: &str) -> Result<i64, Day22Error> {
+    cubes_on(parse(input)?.into_iter())
+}
+
+#[test]
+fn test_cuboid() {
+    let a = Cuboid {
+        min: [10, 10, 10],
+        max: [12, 12, 12],
+    };
+    let b = Cuboid {
+        min: [11, 11, 11],
+        max: [13, 13, 13],
+    };
+    let c = Cuboid {
+        min: [13, 10, 10],
+        max: [14, 12, 12],
+    };
+    assert_eq!(a.volume(), Some(27));
+    assert_eq!(c.volume(), Some(18));
+    assert_eq!(
+        a.intersection(&b),
+        Some(Cuboid {
+            min: [11, 11, 11],
+            max: [12, 12, 12]
+        })
+    );
+    assert_eq!(a.intersection(&b), b.intersection(&a));
+    assert_eq!(a.intersection(&c), None);
+    assert_eq!(a.intersection(&a), Some(a));
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        parse("on x=-20..26,y=-36..17,z=-47..7\noff x=9..11,y=9..11,z=9..11"),
+        Ok(vec![
+            Step {
+                on: true,
+                cuboid: Cuboid {
+                    min: [-20, -36, -47],
+                    max: [26, 17, 7]
+                }
+            },
+            Step {
+                on: false,
+                cuboid: Cuboid {
+                    min: [9, 9, 9],
+                    max: [11, 11, 11]
+                }
+            }
+        ])
+    );
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("on x=10..12,y=10..12,z=10..12\nof x=9..11,y=9..11,z=9..11"),
+        Err(Day22Error::Parse(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        parse("on x=10..12,y=10..12,z=10..12\non x=9..11,y=9..11"),
+        Err(Day22Error::Parse(Position {
+            line: 2,
+            column: 19
+        }))
+    );
+    assert_eq!(
+        parse("on x=10..12,y=10..12,z=10..12 on"),
+        Err(Day22Error::Parse(Position {
+            line: 1,
+            column: 30
+        }))
+    );
+    assert_eq!(
+        parse("on x=10..12,y=12..10,z=10..12"),
+        Err(Day22Error::EmptyCuboid(1))
+    );
+}
+
+#[test]
+fn test_cubes_on_brute_force() {
+    use std::collections::HashSet;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(22);
+    let mut random = |range: i64| rng.gen_range(0, range);
+    let steps: Vec<Step> = (0..30)
+        .map(|_| {
+            let min = [random(10), random(10), random(10)];
+            let max = [0, 1, 2].map(|axis| min[axis] + random(6));
+            Step {
+                on: random(3) != 0,
+                cuboid: Cuboid { min, max },
+            }
+        })
+        .collect();
+
+    let mut on = HashSet::new();
+    for step in steps.iter() {
+        for x in step.cuboid.min[0]..=step.cuboid.max[0] {
+            for y in step.cuboid.min[1]..=step.cuboid.max[1] {
+                for z in step.cuboid.min[2]..=step.cuboid.max[2] {
+                    if step.on {
+                        on.insert((x, y, z));
+                    } else {
+                        on.remove(&(x, y, z));
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(cubes_on(steps.into_iter()), Ok(on.len() as i64));
+}
+
+#[test]
+fn test_part_1_example() {
+    let input = "on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10";
+    assert_eq!(part_1(input), Ok(39));
+}
+
+#[test]
+fn test_part_1_outside_region() {
+    let input = "on x=10..12,y=10..12,z=10..12
+on x=-54..-49,y=10..10,z=10..10
+on x=100..200,y=10..10,z=10..10";
+    assert_eq!(part_1(input), Ok(27 + 2));
+    assert_eq!(part_2(input), Ok(27 + 6 + 101));
+}
+
+#[test]
+fn test_part_2_example() {
+    let input = "on x=-5..47,y=-31..22,z=-19..33
+on x=-44..5,y=-27..21,z=-14..35
+on x=-49..-1,y=-11..42,z=-10..38
+on x=-20..34,y=-40..6,z=-44..1
+off x=26..39,y=40..50,z=-2..11
+on x=-41..5,y=-41..6,z=-36..8
+off x=-43..-33,y=-45..-28,z=7..25
+on x=-33..15,y=-32..19,z=-34..11
+off x=35..47,y=-46..-34,z=-11..5
+on x=-14..36,y=-6..44,z=-16..29
+on x=-57795..-6158,y=29564..72030,z=20435..90618
+on x=36731..105352,y=-21140..28532,z=16094..90401
+on x=30999..107136,y=-53464..15513,z=8553..71215
+on x=13528..83982,y=-99403..-27377,z=-24141..23996
+on x=-72682..-12347,y=18159..111354,z=7391..80950
+on x=-1060..80757,y=-65301..-20884,z=-103788..-16709
+on x=-83015..-9461,y=-72160..-8347,z=-81239..-26856
+on x=-52752..22273,y=-49450..9096,z=54442..119054
+on x=-29982..40483,y=-108474..-28371,z=-24328..38471
+on x=-4958..62750,y=40422..118853,z=-7672..65583
+on x=55694..108686,y=-43367..46958,z=-26781..48729
+on x=-98497..-18186,y=-63569..3412,z=1232..88485
+on x=-726..56291,y=-62629..13224,z=18033..85226
+on x=-110886..-34664,y=-81338..-8658,z=8914..63723
+on x=-55829..24974,y=-16897..54165,z=-121762..-28058
+on x=-65152..-11147,y=22489..91432,z=-58782..1780
+on x=-120100..-32970,y=-46592..27473,z=-11695..61039
+on x=-18631..37533,y=-124565..-50804,z=-35667..28308
+on x=-57817..18248,y=49321..117703,z=5745..55881
+on x=14781..98692,y=-1341..70827,z=15753..70151
+on x=-34419..55919,y=-19626..40991,z=39015..114138
+on x=-60785..11593,y=-56135..2999,z=-95368..-26915
+on x=-32178..58085,y=17647..101866,z=-91405..-8878
+on x=-53655..12091,y=50097..105568,z=-75335..-4862
+on x=-111166..-40997,y=-71714..2688,z=5609..50954
+on x=-16602..70118,y=-98693..-44401,z=5197..76897
+on x=16383..101554,y=4615..83635,z=-44907..18747
+off x=-95822..-15171,y=-19987..48940,z=10804..104439
+on x=-89813..-14614,y=16069..88491,z=-3297..45228
+on x=41075..99376,y=-20427..49978,z=-52012..13762
+on x=-21330..50085,y=-17944..62733,z=-112280..-30197
+on x=-16478..35915,y=36008..118594,z=-7885..47086
+off x=-98156..-27851,y=-49952..43171,z=-99005..-8456
+off x=2032..69770,y=-71013..4824,z=7471..94418
+on x=43670..120875,y=-42068..12382,z=-24787..38892
+off x=37514..111226,y=-45862..25743,z=-16714..54663
+off x=25699..97951,y=-30668..59918,z=-15349..69697
+off x=-44271..17935,y=-9516..60759,z=49131..112598
+on x=-61695..-5813,y=40978..94975,z=8655..80240
+off x=-101086..-9439,y=-7088..67543,z=33935..83858
+off x=18020..114017,y=-48931..32606,z=21474..89843
+off x=-77139..10506,y=-89994..-18797,z=-80..59318
+off x=8476..79288,y=-75520..11602,z=-96624..-24783
+on x=-47488..-1262,y=24338..100707,z=16292..72967
+off x=-84341..13987,y=2429..92914,z=-90671..-1318
+off x=-37810..49457,y=-71013..-7894,z=-105357..-13188
+off x=-27365..46395,y=31009..98017,z=15428..76570
+off x=-70369..-16548,y=22648..78696,z=-1892..86821
+on x=-53470..21291,y=-120233..-33476,z=-44150..38147
+off x=-93533..-4276,y=-16170..68771,z=-104985..-24507";
+    assert_eq!(part_1(input), Ok(474140));
+    assert_eq!(part_2(input), Ok(2758514936282235));
+}
+
+#[test]
+fn test_overflow() {
+    let wide = format!("on x={0}..{1},y={0}..{1},z={0}..{1}", i64::MIN, i64::MAX);
+    assert_eq!(part_2(&wide), Err(Day22Error::Overflow));
+    let cuboid = |size: i64| format!("on x=0..{0},y=0..{0},z=0..{0}", size - 1);
+    // 2^21 cubed is too many, 2^20 cubed is not
+    assert_eq!(part_2(&cuboid(1 << 21)), Err(Day22Error::Overflow));
+    assert_eq!(part_2(&cuboid(1 << 20)), Ok(1 << 60));
+    // The terms do not fit in an i64, but the answer does
+    let input = format!(
+        "{}\noff x=1..{1},y=0..{1},z=0..{1}",
+        cuboid(1 << 21),
+        (1 << 21) - 1
+    );
+    assert_eq!(part_2(&input), Ok(1 << 42));
+    assert_eq!(part_1(&wide), Ok(101 * 101 * 101));
+}
@@ -1,10 +1,45 @@
-use std::{collections::{BinaryHeap, HashMap}, num::NonZeroU8};
+//! Day 23: Amphipod. Finds the least energy needed to sort the amphipods.
+//!
+//! ```
+//! use advent_of_code_2021::day23;
+//!
+//! let input = concat!(
+//!     "#############\n",
+//!     "#...........#\n",
+//!     "###B#C#B#D###\n",
+//!     "  #A#D#C#A#\n",
+//!     "  #########",
+//! );
+//! assert_eq!(day23::part_1(input), Ok(12521));
+//! ```
+
+use std::cmp::Ordering;
 use std::{
-    cmp::Ordering
+    collections::{BinaryHeap, HashMap},
+    num::NonZeroU8,
 };
 
 use arrayvec::ArrayVec;
-use nom::{character::complete::{line_ending, one_of, not_line_ending}, IResult, sequence::terminated, bytes::complete::{tag, take}, combinator::map};
+use nom::{
+    bytes::complete::{tag, take},
+    character::complete::{line_ending, not_line_ending, one_of},
+    combinator::{all_consuming, map},
+    sequence::terminated,
+    IResult,
+};
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day23Error {
+    #[error("{0}: expected a burrow diagram")]
+    Parse(Position),
+    #[error("the rooms do not hold equally many amphipods of every type")]
+    UnbalancedAmphipods,
+    #[error("the amphipods cannot be organized")]
+    NoSolution,
+}
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 struct Amphipod(NonZeroU8);
@@ -17,29 +52,41 @@ impl Default for Amphipod {
 
 impl Amphipod {
     fn parse(input: &str) -> IResult<&str, Self> {
-        map(one_of("ABCD"), |c| Amphipod(NonZeroU8::try_from(c as u8).unwrap()))(input)
+        map(one_of("ABCD"), |c| {
+            Amphipod(NonZeroU8::try_from(c as u8).unwrap())
+        })(input)
     }
 
     const fn energy_per_step(&self) -> usize {
-        if self == Amphipod(NonZeroU8::try_from(b'A').unwrap()) {
-            1
-        } else if self == Amphipod(NonZeroU8::try_from(b'B').unwrap()) {
-            10
-        } else if self == Amphipod(NonZeroU8::try_from(b'C').unwrap()) {
-            100
-        } else {
-            1000
+        match self.0.get() {
+            b'A' => 1,
+            b'B' => 10,
+            b'C' => 100,
+            _ => 1000,
         }
     }
+
+    /// The index of the room this amphipod should end up in.
+    const fn target_room(&self) -> usize {
+        (self.0.get() - b'A') as usize
+    }
 }
 
 const ROOM_COUNT: usize = 4;
-const HALL_SPOTS: usize = ROOM_COUNT + 1;
+/// The hallway squares an amphipod may stop on. The squares directly in
+/// front of a room are excluded.
+const HALL_SPOTS: usize = ROOM_COUNT + 3;
+const HALL_X: [usize; HALL_SPOTS] = [0, 1, 3, 5, 7, 9, 10];
+
+const fn room_x(room: usize) -> usize {
+    2 + 2 * room
+}
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
 struct Diagram<const ROOM_DEPTH: usize> {
+    /// The amphipods in every room, bottom first.
     rooms: [ArrayVec<Amphipod, ROOM_DEPTH>; ROOM_COUNT],
-    hall_positions: [Option<Amphipod>; HALL_SPOTS]
+    hall_positions: [Option<Amphipod>; HALL_SPOTS],
 }
 
 impl<const ROOM_DEPTH: usize> Diagram<ROOM_DEPTH> {
@@ -54,7 +101,7 @@ impl<const ROOM_DEPTH: usize> Diagram<ROOM_DEPTH> {
         // #############
         let (input, _) = terminated(not_line_ending, line_ending)(input)?;
         // #...........#
-        let (mut input, _) = terminated(not_line_ending, line_ending)(input)?; 
+        let (mut input, _) = terminated(not_line_ending, line_ending)(input)?;
         for depth in 0..ROOM_DEPTH {
             let (new_input, _) = take(3_usize)(input)?;
             input = new_input;
@@ -75,43 +122,150 @@ impl<const ROOM_DEPTH: usize> Diagram<ROOM_DEPTH> {
         Ok((input, diagram))
     }
 
+    /// Whether the room only contains amphipods that belong there.
+    fn room_is_settled(&self, room: usize) -> bool {
+        self.rooms[room].iter().all(|a| a.target_room() == room)
+    }
+
+    /// Whether the hallway between `hall` and the entrance of `room` is free.
+    /// The spot `hall` itself is not checked.
+    fn hall_is_clear(&self, room: usize, hall: usize) -> bool {
+        let x = room_x(room);
+        self.hall_positions
+            .iter()
+            .zip(HALL_X)
+            .enumerate()
+            .filter(|(other_hall, (_, other_x))| {
+                *other_hall != hall && (x.min(HALL_X[hall])..=x.max(HALL_X[hall])).contains(other_x)
+            })
+            .all(|(_, (spot, _))| spot.is_none())
+    }
+
+    /// An admissible estimate: every amphipod walks straight to the entrance
+    /// of its room and takes one step in. Amphipods that still have to leave
+    /// their room, even if it is their own, first walk into the hallway.
     fn estimate_remaining_cost(&self) -> usize {
-        unimplemented!()
+        let hall_cost: usize = self
+            .hall_positions
+            .iter()
+            .zip(HALL_X)
+            .filter_map(|(spot, x)| spot.map(|a| (a, x)))
+            .map(|(a, x)| (x.abs_diff(room_x(a.target_room())) + 1) * a.energy_per_step())
+            .sum();
+        let room_cost: usize = self
+            .rooms
+            .iter()
+            .enumerate()
+            .map(|(room, amphipods)| {
+                // Amphipods at the bottom that are already home can stay
+                let settled = amphipods
+                    .iter()
+                    .take_while(|a| a.target_room() == room)
+                    .count();
+                amphipods
+                    .iter()
+                    .enumerate()
+                    .skip(settled)
+                    .map(|(depth, a)| {
+                        let steps_out = ROOM_DEPTH - depth;
+                        let steps_across = room_x(room).abs_diff(room_x(a.target_room())).max(2);
+                        (steps_out + steps_across + 1) * a.energy_per_step()
+                    })
+                    .sum::<usize>()
+            })
+            .sum();
+        hall_cost + room_cost
     }
 
     fn possible_moves(&self) -> impl Iterator<Item = Move> {
-        unimplemented!()
+        let mut moves = ArrayVec::<Move, { ROOM_COUNT * HALL_SPOTS }>::new();
+
+        // Moving an amphipod into its room is never worse than any other move,
+        // so only consider those if there are any.
+        for (hall, spot) in self.hall_positions.iter().enumerate() {
+            if let Some(amphipod) = *spot {
+                let room = amphipod.target_room();
+                if self.room_is_settled(room) && self.hall_is_clear(room, hall) {
+                    moves.push(Move {
+                        r#type: MoveType::HallToRoom,
+                        room: room as u8,
+                        hall: hall as u8,
+                        amphipod,
+                    });
+                }
+            }
+        }
+        if !moves.is_empty() {
+            return moves.into_iter();
+        }
+
+        for (room, amphipods) in self.rooms.iter().enumerate() {
+            if self.room_is_settled(room) {
+                continue;
+            }
+            let amphipod = *amphipods.last().unwrap();
+            for hall in 0..HALL_SPOTS {
+                if self.hall_positions[hall].is_none() && self.hall_is_clear(room, hall) {
+                    moves.push(Move {
+                        r#type: MoveType::RoomToHall,
+                        room: room as u8,
+                        hall: hall as u8,
+                        amphipod,
+                    });
+                }
+            }
+        }
+        moves.into_iter()
     }
 
     fn is_solution(&self) -> bool {
-        unimplemented!()
+        self.rooms
+            .iter()
+            .enumerate()
+            .all(|(room, amphipods)| amphipods.is_full() && self.room_is_settled(room))
     }
 
     fn cost_of_move(&self, r#move: Move) -> usize {
-        unimplemented!()
+        let room = usize::from(r#move.room);
+        let occupants = self.rooms[room].len();
+        let steps_in_room = match r#move.r#type {
+            MoveType::RoomToHall => ROOM_DEPTH - occupants + 1,
+            MoveType::HallToRoom => ROOM_DEPTH - occupants,
+        };
+        let steps_in_hall = room_x(room).abs_diff(HALL_X[usize::from(r#move.hall)]);
+        (steps_in_room + steps_in_hall) * r#move.amphipod.energy_per_step()
     }
 
     fn after_move(&self, r#move: Move) -> Self {
-        unimplemented!()
+        let mut diagram = self.clone();
+        let room = &mut diagram.rooms[usize::from(r#move.room)];
+        let hall = &mut diagram.hall_positions[usize::from(r#move.hall)];
+        match r#move.r#type {
+            MoveType::RoomToHall => *hall = room.pop(),
+            MoveType::HallToRoom => room.push(hall.take().unwrap()),
+        }
+        diagram
     }
 }
 
+#[derive(Copy, Clone, Debug)]
 enum MoveType {
     RoomToHall,
-    HallToRoom
+    HallToRoom,
 }
 
+#[derive(Copy, Clone, Debug)]
 struct Move {
     r#type: MoveType,
     room: u8,
     hall: u8,
-    amphipod: Amphipod
+    amphipod: Amphipod,
 }
 
 #[derive(Eq, PartialEq)]
 struct BinaryHeapItem<const ROOM_DEPTH: usize> {
     item: Diagram<ROOM_DEPTH>,
-    cost_estimate: usize
+    cost_estimate: usize,
 }
 
 impl<const ROOM_DEPTH: usize> Ord for BinaryHeapItem<ROOM_DEPTH> {
@@ -126,27 +280,34 @@ impl<const ROOM_DEPTH: usize> PartialOrd for BinaryHeapItem<ROOM_DEPTH> {
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    let (_, diagram) = Diagram::parse(input).unwrap();
+fn least_energy<const ROOM_DEPTH: usize>(diagram: Diagram<ROOM_DEPTH>) -> Option<usize> {
     let mut open_set = BinaryHeap::new();
     let mut g_score = HashMap::new();
-    g_score.insert(diagram, 0);
     open_set.push(BinaryHeapItem {
-        item: diagram,
-        cost_estimate: diagram.estimate_remaining_cost()
+        cost_estimate: diagram.estimate_remaining_cost(),
+        item: diagram.clone(),
     });
+    g_score.insert(diagram, 0);
 
-    while let Some(BinaryHeapItem { item , .. }) = open_set.pop() {
+    while let Some(BinaryHeapItem {
+        item,
+        cost_estimate,
+    }) = open_set.pop()
+    {
         let current_score = *g_score.get(&item).unwrap();
+        if current_score + item.estimate_remaining_cost() < cost_estimate {
+            // A cheaper path to this diagram has been found since it was pushed
+            continue;
+        }
         if item.is_solution() {
-            return current_score;
+            return Some(current_score);
         }
         for possible_move in item.possible_moves() {
             let tentative_score = current_score + item.cost_of_move(possible_move);
-            let after_move = diagram.after_move(possible_move);
+            let after_move = item.after_move(possible_move);
             if tentative_score < *g_score.get(&after_move).unwrap_or(&usize::MAX) {
-                g_score.insert(after_move, tentative_score);
                 let cost_estimate = tentative_score + after_move.estimate_remaining_cost();
+                g_score.insert(after_move.clone(), tentative_score);
                 open_set.push(BinaryHeapItem {
                     item: after_move,
                     cost_estimate,
@@ -154,9 +315,52 @@ pub fn part_1(input: &str) -> usize {
             }
         }
     }
-    unreachable!("No solution")
+    None
+}
+
+/// Parse the complete diagram, reporting errors at their position in `input`.
+fn parse<const ROOM_DEPTH: usize>(input: &str) -> Result<Diagram<ROOM_DEPTH>, Day23Error> {
+    let (_, diagram) = all_consuming(Diagram::parse)(input)
+        .map_err(|e| Day23Error::Parse(Position::of_nom_error(input.as_bytes(), &e)))?;
+    // Every room should fit exactly the amphipods that belong there
+    let mut counts = [0; ROOM_COUNT];
+    for amphipod in diagram.rooms.iter().flatten() {
+        counts[amphipod.target_room()] += 1;
+    }
+    if counts.iter().any(|count| *count != ROOM_DEPTH) {
+        return Err(Day23Error::UnbalancedAmphipods);
+    }
+    Ok(diagram)
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day23Error> {
+    least_energy(parse::<2>(&normalize::line_endings(input))?).ok_or(Day23Error::NoSolution)
 }
 
+/// The lines that are folded away in the input for part 2.
+const UNFOLDED_LINES: &str = "  #D#C#B#A#
+  #D#B#A#C#
+";
+
+pub fn part_2(input: &str) -> Result<usize, Day23Error> {
+    let input = normalize::line_endings(input);
+    // Insert the extra lines after the first row of amphipods
+    let split = input
+        .match_indices('\n')
+        .nth(2)
+        .map(|(pos, _)| pos + 1)
+        .ok_or_else(|| Day23Error::Parse(Position::of(input.as_bytes(), input.len())))?;
+    let unfolded = format!("{}{}{}", &input[..split], UNFOLDED_LINES, &input[split..]);
+    let diagram = parse::<4>(&unfolded).map_err(|e| match e {
+        // Report the position in the folded input
+        Day23Error::Parse(mut position) if position.line > 3 => {
+            position.line -= UNFOLDED_LINES.lines().count();
+            Day23Error::Parse(position)
+        }
+        e => e,
+    })?;
+    least_energy(diagram).ok_or(Day23Error::NoSolution)
+}
 
 #[test]
 fn test_parse() {
@@ -166,15 +370,69 @@ fn test_parse() {
   #A#D#C#A#
   #########";
 
-  let pod = |a: u8| Amphipod(a.try_into().unwrap());
-
-  assert_eq!(Diagram::parse(input), Ok(("", Diagram {
-      rooms: [
-          ArrayVec::from([pod(b'A'), pod(b'B')]),
-          ArrayVec::from([pod(b'D'), pod(b'C')]),
-          ArrayVec::from([pod(b'C'), pod(b'B')]),
-          ArrayVec::from([pod(b'A'), pod(b'D')])
-      ],
-      hall_positions: [None; HALL_SPOTS]
-  })));
-}
\ No newline at end of file
+    let pod = |a: u8| Amphipod(a.try_into().unwrap());
+
+    assert_eq!(
+        Diagram::parse(input),
+        Ok((
+            "",
+            Diagram {
+                rooms: [
+                    ArrayVec::from([pod(b'A'), pod(b'B')]),
+                    ArrayVec::from([pod(b'D'), pod(b'C')]),
+                    ArrayVec::from([pod(b'C'), pod(b'B')]),
+                    ArrayVec::from([pod(b'A'), pod(b'D')])
+                ],
+                hall_positions: [None; HALL_SPOTS]
+            }
+        ))
+    );
+}
+
+#[test]
+fn test_parse_error() {
+    let input = "#############
+#...........#
+###B#C#B#D###
+  #A#E#C#A#
+  #########";
+    assert_eq!(
+        part_1(input),
+        Err(Day23Error::Parse(Position { line: 4, column: 6 }))
+    );
+    assert_eq!(
+        part_2(input),
+        Err(Day23Error::Parse(Position { line: 4, column: 6 }))
+    );
+    assert_eq!(
+        part_1("#############\n#...........#\n"),
+        Err(Day23Error::Parse(Position {
+            line: 2,
+            column: 14
+        }))
+    );
+    assert_eq!(
+        part_1(&input.replace('E', "D").replace("#B#D###", "#B#A###")),
+        Err(Day23Error::UnbalancedAmphipods)
+    );
+}
+
+#[test]
+fn test_part_1_example() {
+    let input = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+    assert_eq!(part_1(input), Ok(12521));
+}
+
+#[test]
+fn test_part_2_example() {
+    let input = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+    assert_eq!(part_2(input), Ok(44169));
+}
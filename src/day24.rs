@@ -0,0 +1,611 @@
+//! Day 24: Arithmetic Logic Unit. Finds the largest and the smallest model
+//! numbers that MONAD accepts.
+//!
+//! ```
+//! use advent_of_code_2021::day24;
+//!
+//! // Accepts even numbers
+//! let program = day24::parse("inp w\nadd z w\nmod z 2").unwrap();
+//! assert_eq!(day24::is_valid(&program, 4), Ok(true));
+//! assert_eq!(day24::is_valid(&program, 3), Ok(false));
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::normalize;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    fn parse(s: &str) -> Option<Register> {
+        match s {
+            "w" => Some(Register::W),
+            "x" => Some(Register::X),
+            "y" => Some(Register::Y),
+            "z" => Some(Register::Z),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Operand {
+    Register(Register),
+    Literal(i64),
+}
+
+impl Operand {
+    fn parse(s: &str) -> Option<Operand> {
+        Register::parse(s)
+            .map(Operand::Register)
+            .or_else(|| s.parse().ok().map(Operand::Literal))
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+#[error("invalid instruction: {0:?}")]
+pub struct ParseInstructionError(String);
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseInstructionError(s.to_string());
+        let mut parts = s.split_ascii_whitespace();
+        let op = parts.next().ok_or_else(error)?;
+        let a = parts.next().and_then(Register::parse).ok_or_else(error)?;
+        let instruction = if op == "inp" {
+            Instruction::Inp(a)
+        } else {
+            let b = parts.next().and_then(Operand::parse).ok_or_else(error)?;
+            match op {
+                "add" => Instruction::Add(a, b),
+                "mul" => Instruction::Mul(a, b),
+                "div" => Instruction::Div(a, b),
+                "mod" => Instruction::Mod(a, b),
+                "eql" => Instruction::Eql(a, b),
+                _ => return Err(error()),
+            }
+        };
+        if parts.next().is_some() {
+            return Err(error());
+        }
+        Ok(instruction)
+    }
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day24Error {
+    #[error("line {line}: {source}")]
+    InvalidInstruction {
+        line: usize,
+        source: ParseInstructionError,
+    },
+    #[error(transparent)]
+    Analysis(#[from] AnalysisError),
+}
+
+pub fn parse(input: &str) -> Result<Vec<Instruction>, Day24Error> {
+    normalize::line_endings(input)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|source| Day24Error::InvalidInstruction {
+                    line: i + 1,
+                    source,
+                })
+        })
+        .collect()
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum AluError {
+    #[error("instruction {0}: division by zero")]
+    DivisionByZero(usize),
+    #[error("instruction {0}: modulo with a negative or zero operand")]
+    InvalidModulo(usize),
+    #[error("instruction {0}: no input left")]
+    MissingInput(usize),
+    #[error("instruction {0}: the result overflows")]
+    Overflow(usize),
+}
+
+/// The arithmetic logic unit of the submarine.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Alu {
+    registers: [i64; 4],
+}
+
+impl fmt::Display for Alu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [w, x, y, z] = self.registers;
+        write!(f, "w={} x={} y={} z={}", w, x, y, z)
+    }
+}
+
+impl Alu {
+    pub fn get(&self, register: Register) -> i64 {
+        self.registers[register.index()]
+    }
+
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(register) => self.get(register),
+            Operand::Literal(value) => value,
+        }
+    }
+
+    /// Execute a single instruction. `position` is only used for reporting
+    /// errors, and is the 1-based number of the instruction in the program.
+    pub fn execute(
+        &mut self,
+        position: usize,
+        instruction: Instruction,
+        input: &mut impl Iterator<Item = i64>,
+    ) -> Result<(), AluError> {
+        let (a, result) = match instruction {
+            Instruction::Inp(a) => (a, input.next().ok_or(AluError::MissingInput(position))?),
+            Instruction::Add(a, b) => (
+                a,
+                self.get(a)
+                    .checked_add(self.value(b))
+                    .ok_or(AluError::Overflow(position))?,
+            ),
+            Instruction::Mul(a, b) => (
+                a,
+                self.get(a)
+                    .checked_mul(self.value(b))
+                    .ok_or(AluError::Overflow(position))?,
+            ),
+            Instruction::Div(a, b) => match self.value(b) {
+                0 => return Err(AluError::DivisionByZero(position)),
+                b => (
+                    a,
+                    self.get(a)
+                        .checked_div(b)
+                        .ok_or(AluError::Overflow(position))?,
+                ),
+            },
+            Instruction::Mod(a, b) => match (self.get(a), self.value(b)) {
+                (a_value, b) if a_value < 0 || b <= 0 => {
+                    return Err(AluError::InvalidModulo(position))
+                }
+                (a_value, b) => (a, a_value % b),
+            },
+            Instruction::Eql(a, b) => (a, i64::from(self.get(a) == self.value(b))),
+        };
+        self.registers[a.index()] = result;
+        Ok(())
+    }
+
+    pub fn run(
+        &mut self,
+        program: &[Instruction],
+        input: impl IntoIterator<Item = i64>,
+    ) -> Result<(), AluError> {
+        self.run_traced(program, input, |_, _, _| {})
+    }
+
+    /// Run the program, calling `trace` with the position, the instruction and
+    /// the state of the ALU after every instruction. Positions start at 1,
+    /// like the lines of a program without empty lines.
+    pub fn run_traced(
+        &mut self,
+        program: &[Instruction],
+        input: impl IntoIterator<Item = i64>,
+        mut trace: impl FnMut(usize, Instruction, &Alu),
+    ) -> Result<(), AluError> {
+        let mut input = input.into_iter();
+        for (position, instruction) in (1..).zip(program.iter().copied()) {
+            self.execute(position, instruction, &mut input)?;
+            trace(position, instruction, self);
+        }
+        Ok(())
+    }
+}
+
+/// Convert a model number to the digits fed to MONAD.
+fn digits(model_number: u64) -> impl Iterator<Item = i64> {
+    let digits: Vec<i64> = model_number
+        .to_string()
+        .bytes()
+        .map(|d| i64::from(d - b'0'))
+        .collect();
+    digits.into_iter()
+}
+
+/// Whether MONAD accepts the model number.
+pub fn is_valid(program: &[Instruction], model_number: u64) -> Result<bool, AluError> {
+    let mut alu = Alu::default();
+    alu.run(program, digits(model_number))?;
+    Ok(alu.get(Register::Z) == 0)
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum AnalysisError {
+    #[error("block {0} does not have the expected shape")]
+    UnexpectedBlock(usize),
+    #[error("block {0} can never pop the digit it pushed")]
+    UnbalancedBlock(usize),
+    #[error("block {0} only pushes its digit for some inputs")]
+    ConditionalPush(usize),
+    #[error("the program has no blocks")]
+    NoBlocks,
+    #[error("the program has {0} blocks, but model numbers have at most 19 digits")]
+    TooManyDigits(usize),
+    #[error("no model number is accepted")]
+    NoSolution,
+}
+
+/// The parameters that differ between the blocks of MONAD. Every block
+/// reads a digit `w` and, treating `z` as a stack of base 26 digits, pops if
+/// `divisor` is 26. If `w` is not the popped (or peeked) value plus `check`,
+/// it pushes `w + offset`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Block {
+    divisor: i64,
+    check: i64,
+    offset: i64,
+}
+
+/// The instructions of a block, with `None` marking the parameters.
+const BLOCK_TEMPLATE: [Option<Instruction>; 18] = {
+    use Instruction::*;
+    use Operand::{Literal, Register as Reg};
+    use Register::{W, X, Y, Z};
+    [
+        Some(Inp(W)),
+        Some(Mul(X, Literal(0))),
+        Some(Add(X, Reg(Z))),
+        Some(Mod(X, Literal(26))),
+        None, // div z {divisor}
+        None, // add x {check}
+        Some(Eql(X, Reg(W))),
+        Some(Eql(X, Literal(0))),
+        Some(Mul(Y, Literal(0))),
+        Some(Add(Y, Literal(25))),
+        Some(Mul(Y, Reg(X))),
+        Some(Add(Y, Literal(1))),
+        Some(Mul(Z, Reg(Y))),
+        Some(Mul(Y, Literal(0))),
+        Some(Add(Y, Reg(W))),
+        None, // add y {offset}
+        Some(Mul(Y, Reg(X))),
+        Some(Add(Z, Reg(Y))),
+    ]
+};
+
+impl Block {
+    fn parse(index: usize, instructions: &[Instruction]) -> Result<Block, AnalysisError> {
+        let error = AnalysisError::UnexpectedBlock(index);
+        if instructions.len() != BLOCK_TEMPLATE.len()
+            || BLOCK_TEMPLATE
+                .iter()
+                .zip(instructions)
+                .any(|(expected, found)| matches!(expected, Some(e) if e != found))
+        {
+            return Err(error);
+        }
+        match (instructions[4], instructions[5], instructions[15]) {
+            (
+                Instruction::Div(Register::Z, Operand::Literal(divisor @ (1 | 26))),
+                Instruction::Add(Register::X, Operand::Literal(check)),
+                Instruction::Add(Register::Y, Operand::Literal(offset)),
+            ) => Ok(Block {
+                divisor,
+                check,
+                offset,
+            }),
+            _ => Err(error),
+        }
+    }
+}
+
+/// The largest and smallest model numbers accepted by MONAD.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ModelNumbers {
+    pub largest: u64,
+    pub smallest: u64,
+}
+
+/// Find the accepted model numbers by pairing up the blocks that push a digit
+/// with the blocks that pop it again. Every pair constrains its two digits to
+/// `later = earlier + offset + check`.
+pub fn solve(program: &[Instruction]) -> Result<ModelNumbers, AnalysisError> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for end in (1..=program.len())
+        .filter(|end| *end == program.len() || matches!(program[*end], Instruction::Inp(_)))
+    {
+        blocks.push(Block::parse(blocks.len(), &program[start..end])?);
+        start = end;
+    }
+    match blocks.len() {
+        0 => return Err(AnalysisError::NoBlocks),
+        // Larger model numbers do not fit in a u64
+        len @ 20.. => return Err(AnalysisError::TooManyDigits(len)),
+        _ => {}
+    }
+
+    let mut largest = vec![0; blocks.len()];
+    let mut smallest = vec![0; blocks.len()];
+    let mut stack = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if block.divisor == 1 {
+            // A digit is always pushed if the check can never succeed
+            if block.check < 10 {
+                return Err(AnalysisError::ConditionalPush(i));
+            }
+            stack.push((i, block.offset));
+        } else {
+            let (j, offset) = stack.pop().ok_or(AnalysisError::UnbalancedBlock(i))?;
+            let difference = offset + block.check;
+            if difference.abs() > 8 {
+                return Err(AnalysisError::NoSolution);
+            }
+            largest[j] = 9.min(9 - difference);
+            largest[i] = largest[j] + difference;
+            smallest[j] = 1.max(1 - difference);
+            smallest[i] = smallest[j] + difference;
+        }
+    }
+    if let Some((j, _)) = stack.pop() {
+        return Err(AnalysisError::UnbalancedBlock(j));
+    }
+
+    let to_number = |digits: Vec<i64>| digits.into_iter().fold(0, |acc, d| 10 * acc + d as u64);
+    Ok(ModelNumbers {
+        largest: to_number(largest),
+        smallest: to_number(smallest),
+    })
+}
+
+pub fn part_1(input: &str) -> Result<u64, Day24Error> {
+    Ok(solve(&parse(input)?)?.largest)
+}
+
+pub fn part_2(input: &str) -> Result<u64, Day24Error> {
+    Ok(solve(&parse(input)?)?.smallest)
+}
+
+/// Generate a MONAD program from its block parameters.
+#[cfg(test)]
+fn monad(blocks: &[(i64, i64, i64)]) -> String {
+    blocks
+        .iter()
+        .map(|(divisor, check, offset)| {
+            format!(
+                "inp w
+mul x 0
+add x z
+mod x 26
+div z {}
+add x {}
+eql x w
+eql x 0
+mul y 0
+add y 25
+mul y x
+add y 1
+mul z y
+mul y 0
+add y w
+add y {}
+mul y x
+add z y
+",
+                divisor, check, offset
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_negate() {
+    let program = parse("inp x\nmul x -1").unwrap();
+    let mut alu = Alu::default();
+    alu.run(&program, [7]).unwrap();
+    assert_eq!(alu.get(Register::X), -7);
+}
+
+#[test]
+fn test_three_times() {
+    let program = parse(
+        "inp z
+inp x
+mul z 3
+eql z x",
+    )
+    .unwrap();
+    let mut alu = Alu::default();
+    alu.run(&program, [3, 9]).unwrap();
+    assert_eq!(alu.get(Register::Z), 1);
+    let mut alu = Alu::default();
+    alu.run(&program, [3, 8]).unwrap();
+    assert_eq!(alu.get(Register::Z), 0);
+}
+
+#[test]
+fn test_binary() {
+    let program = parse(
+        "inp w
+add z w
+mod z 2
+div w 2
+add y w
+mod y 2
+div w 2
+add x w
+mod x 2
+div w 2
+mod w 2",
+    )
+    .unwrap();
+    let mut alu = Alu::default();
+    alu.run(&program, [11]).unwrap();
+    assert_eq!(alu.to_string(), "w=1 x=0 y=1 z=1");
+}
+
+#[test]
+fn test_trace() {
+    let program = parse("inp x\nadd x 2\nmul x x").unwrap();
+    let mut values = Vec::new();
+    Alu::default()
+        .run_traced(&program, [3], |position, _, alu| {
+            values.push((position, alu.get(Register::X)))
+        })
+        .unwrap();
+    assert_eq!(values, vec![(1, 3), (2, 5), (3, 25)]);
+}
+
+#[test]
+fn test_errors() {
+    assert!("inp a".parse::<Instruction>().is_err());
+    assert!("add x".parse::<Instruction>().is_err());
+    assert!("sub x 1".parse::<Instruction>().is_err());
+    assert_eq!(
+        parse("inp x\n\nadd x"),
+        Err(Day24Error::InvalidInstruction {
+            line: 3,
+            source: ParseInstructionError("add x".to_string())
+        })
+    );
+    let program = parse("inp x\ninp y\ndiv x y").unwrap();
+    assert_eq!(
+        Alu::default().run(&program, [1]),
+        Err(AluError::MissingInput(2))
+    );
+    assert_eq!(
+        Alu::default().run(&program, [1, 0]),
+        Err(AluError::DivisionByZero(3))
+    );
+    let program = parse("inp x\nmod x -2").unwrap();
+    assert_eq!(
+        Alu::default().run(&program, [1]),
+        Err(AluError::InvalidModulo(2))
+    );
+    let program = parse("inp x\nmul x x\nmul x x\nadd x 1").unwrap();
+    assert_eq!(
+        Alu::default().run(&program, [1 << 16]),
+        Err(AluError::Overflow(3))
+    );
+    assert_eq!(
+        Alu::default().run(&program, [i64::MAX]),
+        Err(AluError::Overflow(2))
+    );
+    let program = parse("inp x\nadd x 1").unwrap();
+    assert_eq!(
+        Alu::default().run(&program, [i64::MAX]),
+        Err(AluError::Overflow(2))
+    );
+    let program = parse("inp x\ninp y\ndiv x y").unwrap();
+    assert_eq!(
+        Alu::default().run(&program, [i64::MIN, -1]),
+        Err(AluError::Overflow(3))
+    );
+}
+
+#[test]
+fn test_solve_brute_force() {
+    let program = parse(&monad(&[
+        (1, 12, 4),
+        (1, 11, 11),
+        (26, -14, 1),
+        (26, -7, 2),
+    ]))
+    .unwrap();
+    let accepted: Vec<u64> = (1111..=9999)
+        .filter(|n| !n.to_string().contains('0'))
+        .filter(|n| is_valid(&program, *n).unwrap())
+        .collect();
+    assert_eq!(
+        solve(&program),
+        Ok(ModelNumbers {
+            largest: *accepted.last().unwrap(),
+            smallest: accepted[0],
+        })
+    );
+}
+
+#[test]
+fn test_solve_full() {
+    let program = parse(&monad(&[
+        (1, 13, 6),
+        (1, 11, 11),
+        (1, 12, 5),
+        (1, 10, 6),
+        (1, 14, 8),
+        (26, -1, 14),
+        (1, 14, 9),
+        (26, -16, 4),
+        (26, -8, 7),
+        (1, 12, 13),
+        (26, -16, 11),
+        (26, -13, 11),
+        (26, -6, 6),
+        (26, -6, 1),
+    ]))
+    .unwrap();
+    let ModelNumbers { largest, smallest } = solve(&program).unwrap();
+    assert!(is_valid(&program, largest).unwrap());
+    assert!(is_valid(&program, smallest).unwrap());
+    assert!(smallest < largest);
+}
+
+#[test]
+fn test_solve_unexpected_program() {
+    let program = parse("inp w\nadd z w").unwrap();
+    assert_eq!(solve(&program), Err(AnalysisError::UnexpectedBlock(0)));
+    assert_eq!(
+        part_1("inp w\nadd z w"),
+        Err(Day24Error::Analysis(AnalysisError::UnexpectedBlock(0)))
+    );
+}
+
+#[test]
+fn test_solve_invalid_program() {
+    assert_eq!(solve(&[]), Err(AnalysisError::NoBlocks));
+    assert_eq!(
+        part_1(""),
+        Err(Day24Error::Analysis(AnalysisError::NoBlocks))
+    );
+    let program = parse(&monad(&[(1, 9, 4), (26, -4, 1)])).unwrap();
+    assert_eq!(solve(&program), Err(AnalysisError::ConditionalPush(0)));
+    let program = parse(&monad(&[(1, 12, 4), (26, -4, 1), (26, -4, 1)])).unwrap();
+    assert_eq!(solve(&program), Err(AnalysisError::UnbalancedBlock(2)));
+    // Model numbers of 20 digits would overflow
+    let pairs = |n| {
+        [(1, 12, 0)]
+            .repeat(n)
+            .into_iter()
+            .chain([(26, 0, 0)].repeat(n))
+    };
+    let program = parse(&monad(&pairs(9).collect::<Vec<_>>())).unwrap();
+    assert_eq!(solve(&program).unwrap().largest, 999_999_999_999_999_999);
+    let program = parse(&monad(&pairs(10).collect::<Vec<_>>())).unwrap();
+    assert_eq!(solve(&program), Err(AnalysisError::TooManyDigits(20)));
+}
@@ -0,0 +1,364 @@
+//! Day 25: Sea Cucumber. Moves the sea cucumbers until they all stop.
+//!
+//! ```
+//! use advent_of_code_2021::day25;
+//!
+//! let input = "v...>>.vv>\n.vv>>.vv..\n>>.>v>...v\n>>v>>.>.v.\nv>v.vv.v..
+//! >.>>..v...\n.vv..>.>v.\nv.v..>>v.v\n....v..v.>";
+//! assert_eq!(day25::part_1(input), Ok(58));
+//! ```
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+const WORD_BITS: usize = 64;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day25Error {
+    #[error("{0}: expected '>', 'v' or '.'")]
+    ExpectedSeaCucumber(Position),
+    #[error("{0}: rows differ in width")]
+    UnequalWidth(Position),
+    #[error("there are no sea cucumbers or empty locations")]
+    Empty,
+    #[error("the sea cucumbers keep moving forever")]
+    NeverStops,
+}
+
+/// The positions of both herds, one bitset per row. Column `x` is bit
+/// `x % 64` of word `x / 64` of a row.
+#[derive(Clone, Eq, PartialEq)]
+struct Herds {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    east: Vec<u64>,
+    south: Vec<u64>,
+}
+
+impl Herds {
+    fn parse(input: &str) -> Result<Self, Day25Error> {
+        let input = normalize::line_endings(input);
+        let width = input.lines().next().map_or(0, |l| l.trim_end().len());
+        let height = input.lines().count();
+        if width == 0 {
+            return Err(Day25Error::Empty);
+        }
+        let words_per_row = width.div_ceil(WORD_BITS);
+        let mut herds = Herds {
+            width,
+            height,
+            words_per_row,
+            east: vec![0; words_per_row * height],
+            south: vec![0; words_per_row * height],
+        };
+        for (y, line) in input.lines().enumerate() {
+            let line = line.trim_end();
+            let error_at = |x: usize| Position {
+                line: y + 1,
+                column: x + 1,
+            };
+            if line.len() != width {
+                return Err(Day25Error::UnequalWidth(error_at(line.len().min(width))));
+            }
+            for (x, b) in line.bytes().enumerate() {
+                let index = y * words_per_row + x / WORD_BITS;
+                match b {
+                    b'>' => herds.east[index] |= 1 << (x % WORD_BITS),
+                    b'v' => herds.south[index] |= 1 << (x % WORD_BITS),
+                    b'.' => {}
+                    _ => return Err(Day25Error::ExpectedSeaCucumber(error_at(x))),
+                }
+            }
+        }
+        Ok(herds)
+    }
+
+    fn row<'a>(&self, bits: &'a [u64], y: usize) -> &'a [u64] {
+        &bits[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Mask for the bits of the last word of a row that are in use.
+    fn last_word_mask(&self) -> u64 {
+        u64::MAX >> (self.words_per_row * WORD_BITS - self.width)
+    }
+
+    /// Move every bit of the row one column east, wrapping around.
+    fn rotate_east(&self, row: &[u64], out: &mut [u64]) {
+        let last = self.words_per_row - 1;
+        let mut carry = (row[last] >> ((self.width - 1) % WORD_BITS)) & 1;
+        for (o, r) in out.iter_mut().zip(row) {
+            *o = r << 1 | carry;
+            carry = r >> (WORD_BITS - 1);
+        }
+        out[last] &= self.last_word_mask();
+    }
+
+    /// Move every bit of the row one column west, wrapping around.
+    fn rotate_west(&self, row: &[u64], out: &mut [u64]) {
+        let last = self.words_per_row - 1;
+        for i in 0..last {
+            out[i] = row[i] >> 1 | row[i + 1] << (WORD_BITS - 1);
+        }
+        out[last] = row[last] >> 1 | (row[0] & 1) << ((self.width - 1) % WORD_BITS);
+    }
+
+    /// Let both herds take a step, using `rows` for the intermediate rows.
+    /// Returns whether any sea cucumber moved.
+    fn step(&mut self, rows: &mut Rows) -> bool {
+        let words = self.words_per_row;
+        let mut moved = false;
+
+        // The east-facing herd
+        for y in 0..self.height {
+            for ((o, e), s) in rows
+                .occupied
+                .iter_mut()
+                .zip(self.row(&self.east, y))
+                .zip(self.row(&self.south, y))
+            {
+                *o = e | s;
+            }
+            self.rotate_west(&rows.occupied, &mut rows.west_of_occupied);
+            for ((m, e), o) in rows
+                .movers
+                .iter_mut()
+                .zip(self.row(&self.east, y))
+                .zip(&rows.west_of_occupied)
+            {
+                *m = e & !o;
+            }
+            self.rotate_east(&rows.movers, &mut rows.moved_to);
+            for ((e, m), t) in self.east[y * words..(y + 1) * words]
+                .iter_mut()
+                .zip(&rows.movers)
+                .zip(&rows.moved_to)
+            {
+                moved |= *m != 0;
+                *e = (*e & !m) | t;
+            }
+        }
+
+        // The south-facing herd, using the state of the first row from before
+        // any of the south-facing sea cucumbers moved.
+        rows.first_row_south
+            .copy_from_slice(self.row(&self.south, 0));
+        for y in 0..self.height {
+            let below = (y + 1) % self.height;
+            let south_below = if below == 0 {
+                &rows.first_row_south
+            } else {
+                self.row(&self.south, below)
+            };
+            for (((m, s), e), s_below) in rows
+                .movers
+                .iter_mut()
+                .zip(self.row(&self.south, y))
+                .zip(self.row(&self.east, below))
+                .zip(south_below)
+            {
+                *m = s & !(e | s_below);
+            }
+            for ((s, m), a) in self.south[y * words..(y + 1) * words]
+                .iter_mut()
+                .zip(&rows.movers)
+                .zip(&rows.movers_above)
+            {
+                moved |= *m != 0;
+                // The movers into the first row are added once the last row
+                // is known
+                *s = (*s & !m) | if y == 0 { 0 } else { *a };
+            }
+            rows.movers_above.copy_from_slice(&rows.movers);
+        }
+        // The sea cucumbers moving off the bottom wrap around to the top
+        for (s, a) in self.south[..words].iter_mut().zip(&rows.movers_above) {
+            *s |= a;
+        }
+        moved
+    }
+}
+
+/// The intermediate rows of a step, allocated once for all steps.
+struct Rows {
+    occupied: Vec<u64>,
+    west_of_occupied: Vec<u64>,
+    movers: Vec<u64>,
+    moved_to: Vec<u64>,
+    first_row_south: Vec<u64>,
+    movers_above: Vec<u64>,
+}
+
+impl Rows {
+    fn new(herds: &Herds) -> Self {
+        let row = vec![0; herds.words_per_row];
+        Rows {
+            occupied: row.clone(),
+            west_of_occupied: row.clone(),
+            movers: row.clone(),
+            moved_to: row.clone(),
+            first_row_south: row.clone(),
+            movers_above: row,
+        }
+    }
+}
+
+impl fmt::Display for Herds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = y * self.words_per_row + x / WORD_BITS;
+                let bit = 1 << (x % WORD_BITS);
+                if self.east[index] & bit != 0 {
+                    write!(f, ">")?;
+                } else if self.south[index] & bit != 0 {
+                    write!(f, "v")?;
+                } else {
+                    write!(f, ".")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Herds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day25Error> {
+    let mut herds = Herds::parse(input)?;
+    let mut rows = Rows::new(&herds);
+    // The herds may keep moving forever, which shows as a repeated state. A
+    // copy of the herds is kept at steps that are powers of two, so that any
+    // cycle is found within a few times its start and length, like in Brent's
+    // algorithm.
+    let mut saved = herds.clone();
+    let mut next_save = 1;
+    let mut step = 0;
+    loop {
+        step += 1;
+        if !herds.step(&mut rows) {
+            return Ok(step);
+        }
+        if herds == saved {
+            return Err(Day25Error::NeverStops);
+        }
+        if step == next_save {
+            saved.clone_from(&herds);
+            next_save *= 2;
+        }
+    }
+}
+
+#[test]
+fn test_single_row() {
+    let mut herds = Herds::parse("...>>>>>...").unwrap();
+    let mut rows = Rows::new(&herds);
+    herds.step(&mut rows);
+    assert_eq!(herds.to_string(), "...>>>>.>..\n");
+    herds.step(&mut rows);
+    assert_eq!(herds.to_string(), "...>>>.>.>.\n");
+}
+
+#[test]
+fn test_wrap_around() {
+    let mut herds = Herds::parse(
+        "...>...
+.......
+......>
+v.....>
+......>
+.......
+..vvv..",
+    )
+    .unwrap();
+    let mut rows = Rows::new(&herds);
+    herds.step(&mut rows);
+    assert_eq!(
+        herds.to_string(),
+        "..vv>..
+.......
+>......
+v.....>
+>......
+.......
+....v..
+"
+    );
+    for _ in 0..3 {
+        herds.step(&mut rows);
+    }
+    assert_eq!(
+        herds.to_string(),
+        ">......
+..v....
+..>.v..
+.>.v...
+...>...
+.......
+v......
+"
+    );
+}
+
+#[test]
+fn test_wide_rows() {
+    // Rows spanning multiple words wrap around correctly
+    let mut row = ".".repeat(130);
+    row.replace_range(129..130, ">");
+    let mut herds = Herds::parse(&row).unwrap();
+    let mut rows = Rows::new(&herds);
+    herds.step(&mut rows);
+    assert_eq!(herds.to_string(), format!(">{}\n", ".".repeat(129)));
+    herds.step(&mut rows);
+    assert_eq!(herds.to_string(), format!(".>{}\n", ".".repeat(128)));
+    for _ in 0..63 {
+        herds.step(&mut rows);
+    }
+    // Across the boundary between the first and second word
+    assert_eq!(herds.to_string().find('>'), Some(64));
+}
+
+#[test]
+fn test_part_1_example() {
+    let input = "v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>";
+    assert_eq!(part_1(input), Ok(58));
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        part_1("v..\n.>.\n..x"),
+        Err(Day25Error::ExpectedSeaCucumber(Position {
+            line: 3,
+            column: 3
+        }))
+    );
+    assert_eq!(
+        part_1("v..\n.>\n..."),
+        Err(Day25Error::UnequalWidth(Position { line: 2, column: 3 }))
+    );
+    assert_eq!(part_1(""), Err(Day25Error::Empty));
+}
+
+#[test]
+fn test_never_stops() {
+    assert_eq!(part_1(">."), Err(Day25Error::NeverStops));
+    assert_eq!(part_1("v\n.\n."), Err(Day25Error::NeverStops));
+    assert_eq!(part_1(">"), Ok(1));
+}
@@ -1,45 +1,190 @@
+//! Day 3: Binary Diagnostic. The puzzle input has numbers of 12 bits, but the
+//! number of bits is taken from the first line, up to 64.
+//!
+//! ```
+//! use advent_of_code_2021::day3::{self, Diagnostics};
+//!
+//! let input = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
+//! assert_eq!(day3::parse::<5>(input).unwrap()[..2], [0b00100, 0b11110]);
+//! assert_eq!(day3::part_1(input), Ok(198));
+//! assert_eq!(
+//!     day3::diagnostics(input),
+//!     Ok(Diagnostics {
+//!         gamma_rate: 22,
+//!         epsilon_rate: 9,
+//!         oxygen_generator_rating: 23,
+//!         co2_scrubber_rating: 10,
+//!     })
+//! );
+//! ```
+
 #[rustfmt::skip]
 #[allow(clippy::all)]
 mod code_golf_part_1;
 
-pub use code_golf_part_1::z as part_1_code_golf;
+use std::ops::AddAssign;
 
-const fn ones_mask(size: usize) -> u32 {
-    let mut mask = 0;
-    let mut pos = 0;
-    loop {
-        mask |= 1 << pos;
-        pos += 1;
-        if pos >= size {
-            break;
-        }
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day3Error {
+    #[error("{0}: expected 0 or 1")]
+    ExpectedBit(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: a number can have at most 64 bits")]
+    TooManyBits(Position),
+}
+
+/// The rates and ratings in a diagnostic report.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Diagnostics {
+    pub gamma_rate: u64,
+    pub epsilon_rate: u64,
+    pub oxygen_generator_rating: u64,
+    pub co2_scrubber_rating: u64,
+}
+
+impl Diagnostics {
+    pub fn power_consumption(&self) -> u128 {
+        u128::from(self.gamma_rate) * u128::from(self.epsilon_rate)
+    }
+
+    pub fn life_support_rating(&self) -> u128 {
+        u128::from(self.oxygen_generator_rating) * u128::from(self.co2_scrubber_rating)
+    }
+}
+
+/// The number of bits of the numbers in the report, which is the length of the
+/// first line.
+fn number_len(input: &[u8]) -> Result<usize, Day3Error> {
+    let number_len = memchr::memchr(b'\n', input).unwrap_or(input.len());
+    match number_len {
+        0 => Err(Day3Error::ExpectedBit(Position::of(input, 0))),
+        1..=64 => Ok(number_len),
+        _ => Err(Day3Error::TooManyBits(Position::of(input, 0))),
+    }
+}
+
+/// Check that numbers of `number_len` bits, as given by the caller, fit in 64
+/// bits.
+fn check_number_len(input: &[u8], number_len: usize) -> Result<(), Day3Error> {
+    if number_len > 64 {
+        return Err(Day3Error::TooManyBits(Position::of(input, 0)));
+    }
+    Ok(())
+}
+
+fn ones_mask(number_len: usize) -> u64 {
+    u64::MAX.checked_shr((64 - number_len) as u32).unwrap_or(0)
+}
+
+/// Check that the chunk of `number_len` bits starting at `start` is followed
+/// by a newline, unless it is the last one.
+fn validate_chunk(
+    input: &[u8],
+    start: usize,
+    chunk: &[u8],
+    number_len: usize,
+) -> Result<(), Day3Error> {
+    let error_at = |offset| Position::of(input, start + offset);
+    if let Some(i) = chunk
+        .iter()
+        .take(number_len)
+        .position(|b| !matches!(b, b'0' | b'1'))
+    {
+        return Err(Day3Error::ExpectedBit(error_at(i)));
+    }
+    match chunk.get(number_len) {
+        None if chunk.len() < number_len => Err(Day3Error::ExpectedBit(error_at(chunk.len()))),
+        None | Some(b'\n') => Ok(()),
+        Some(_) => Err(Day3Error::ExpectedNewline(error_at(number_len))),
     }
-    mask
 }
 
-pub fn part_1_number_len<const NUMBER_SIZE: usize>(input: &str) -> u32 {
-    let mut outputs = [0u16; NUMBER_SIZE];
+/// The gamma rate, counting the ones in every position with counters of type
+/// `T`, which should fit the number of lines.
+fn gamma_rate<T: Copy + Default + AddAssign + From<u8> + Into<u64>>(
+    input: &[u8],
+    number_len: usize,
+) -> Result<u64, Day3Error> {
+    let mut outputs = vec![T::default(); number_len];
 
-    for chunk in input.as_bytes().chunks(NUMBER_SIZE + 1) {
+    for (i, chunk) in input.chunks(number_len + 1).enumerate() {
+        validate_chunk(input, i * (number_len + 1), chunk, number_len)?;
         for (output, num) in outputs.iter_mut().zip(chunk) {
-            *output += u16::from(0b1 & num);
+            *output += T::from(0b1 & num);
         }
     }
 
-    let total = (input.len() + 1) / (NUMBER_SIZE + 1);
-    let gamma = outputs[..NUMBER_SIZE].iter().fold(0, |acc, b| {
-        (acc << 1) + if *b > total as u16 / 2 { 1 } else { 0 }
-    });
-    let epsilon = ones_mask(NUMBER_SIZE) ^ gamma;
-    epsilon * gamma
+    let total = ((input.len() + 1) / (number_len + 1)) as u64;
+    Ok(outputs
+        .into_iter()
+        .fold(0, |acc, b| (acc << 1) | u64::from(b.into() > total / 2)))
+}
+
+/// The gamma and epsilon rates, with counters that are just large enough for
+/// the number of lines.
+fn rates(input: &[u8], number_len: usize) -> Result<(u64, u64), Day3Error> {
+    let lines = (input.len() + 1) / (number_len + 1);
+    let gamma = if lines <= usize::from(u8::MAX) {
+        gamma_rate::<u8>(input, number_len)
+    } else if lines <= usize::from(u16::MAX) {
+        gamma_rate::<u16>(input, number_len)
+    } else if lines <= u32::MAX as usize {
+        gamma_rate::<u32>(input, number_len)
+    } else {
+        gamma_rate::<u64>(input, number_len)
+    }?;
+    Ok((gamma, ones_mask(number_len) ^ gamma))
+}
+
+pub fn part_1_number_len<const NUMBER_SIZE: usize>(input: &str) -> Result<u128, Day3Error> {
+    let input = normalize::line_endings(input);
+    check_number_len(input.as_bytes(), NUMBER_SIZE)?;
+    let (gamma, epsilon) = rates(input.as_bytes(), NUMBER_SIZE)?;
+    Ok(u128::from(epsilon) * u128::from(gamma))
+}
+
+/// The code golf solution to part 1, for numbers of 12 bits. The golfed code
+/// assumes `\n` line endings, so the input is normalized first.
+pub fn part_1_code_golf(input: &str) -> u32 {
+    code_golf_part_1::z(&normalize::line_endings(input))
+}
+
+pub fn part_1(input: &str) -> Result<u128, Day3Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let (gamma, epsilon) = rates(bytes, number_len(bytes)?)?;
+    Ok(u128::from(epsilon) * u128::from(gamma))
 }
 
-pub fn part_1(input: &str) -> u32 {
-    part_1_number_len::<12>(input)
+pub fn part_2(input: &str) -> Result<u128, Day3Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let number_len = number_len(bytes)?;
+    let (oxygen_generator_rating, co2_scrubber_rating) =
+        ratings(&parse_number_len(bytes, number_len)?, number_len);
+    Ok(u128::from(oxygen_generator_rating) * u128::from(co2_scrubber_rating))
 }
 
-pub fn part_2(input: &str) -> u32 {
-    part_2_number_len::<12>(input)
+/// All rates and ratings in the report. The numbers can have up to 64 bits,
+/// and all should have as many bits as the first.
+pub fn diagnostics(input: &str) -> Result<Diagnostics, Day3Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let number_len = number_len(bytes)?;
+    let (gamma_rate, epsilon_rate) = rates(bytes, number_len)?;
+    let (oxygen_generator_rating, co2_scrubber_rating) =
+        ratings(&parse_number_len(bytes, number_len)?, number_len);
+    Ok(Diagnostics {
+        gamma_rate,
+        epsilon_rate,
+        oxygen_generator_rating,
+        co2_scrubber_rating,
+    })
 }
 
 #[derive(PartialEq, Eq)]
@@ -48,14 +193,13 @@ enum RatingType {
     Co2Scrubber,
 }
 
-fn part_2_number_len<const NUMBER_LEN: usize>(input: &str) -> u32 {
-    let numbers: Vec<u32> = read_numbers(input).collect();
-
-    let perform_iter = |rating_type: RatingType| -> u32 {
+/// The oxygen generator and CO2 scrubber ratings.
+fn ratings(numbers: &[u64], number_len: usize) -> (u64, u64) {
+    let perform_iter = |rating_type: RatingType| -> u64 {
         let mut current = 0;
         let mut current_mask = 0;
 
-        for i in (0..NUMBER_LEN).rev() {
+        for i in (0..number_len).rev() {
             let position_mask = 1 << i;
             // Count how many match the current number
             let (zeros, ones, last) = numbers
@@ -83,30 +227,75 @@ fn part_2_number_len<const NUMBER_LEN: usize>(input: &str) -> u32 {
         current
     };
 
-    let oxygen_generator_rating = perform_iter(RatingType::OxygenGenerator);
-    let co2_scrubber_rating = perform_iter(RatingType::Co2Scrubber);
+    (
+        perform_iter(RatingType::OxygenGenerator),
+        perform_iter(RatingType::Co2Scrubber),
+    )
+}
 
-    oxygen_generator_rating * co2_scrubber_rating
+#[cfg(test)]
+fn part_2_number_len<const NUMBER_LEN: usize>(input: &str) -> Result<u128, Day3Error> {
+    let (oxygen_generator_rating, co2_scrubber_rating) =
+        ratings(&parse::<NUMBER_LEN>(input)?, NUMBER_LEN);
+    Ok(u128::from(oxygen_generator_rating) * u128::from(co2_scrubber_rating))
 }
 
-fn read_numbers(input: &str) -> impl Iterator<Item = u32> + '_ {
-    input.lines().map(|line| {
-        line.as_bytes()
-            .iter()
-            .fold(0, |acc, b| (acc << 1) + if *b == b'1' { 1 } else { 0 })
-    })
+fn parse_number_len(input: &[u8], number_len: usize) -> Result<Vec<u64>, Day3Error> {
+    input
+        .chunks(number_len + 1)
+        .enumerate()
+        .map(|(i, chunk)| {
+            validate_chunk(input, i * (number_len + 1), chunk, number_len)?;
+            Ok(chunk[..number_len]
+                .iter()
+                .fold(0, |acc, b| (acc << 1) + u64::from(b & 1)))
+        })
+        .collect()
+}
+
+/// Parse the diagnostic report, consisting of numbers of `NUMBER_LEN` bits.
+pub fn parse<const NUMBER_LEN: usize>(input: &str) -> Result<Vec<u64>, Day3Error> {
+    let input = normalize::line_endings(input);
+    check_number_len(input.as_bytes(), NUMBER_LEN)?;
+    parse_number_len(input.as_bytes(), NUMBER_LEN)
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day3.txt");
-    assert_eq!(part_1(input), 3320834);
+fn test_part_1_code_golf_size() {
+    // Make sure the code golf solution stays golfed
+    const MAX_BYTES: usize = 200;
+    let source = include_str!("day3/code_golf_part_1.rs");
+    assert!(
+        source.len() <= MAX_BYTES,
+        "code golf solution is {} bytes, the maximum is {}",
+        source.len(),
+        MAX_BYTES
+    );
 }
 
 #[test]
-fn test_part_1_code_golf_input() {
-    let input = include_str!("../input/2021/day3.txt");
-    assert_eq!(part_1_code_golf(input), 3320834);
+fn test_parse_error() {
+    assert_eq!(
+        parse::<5>("00100\n11120\n"),
+        Err(Day3Error::ExpectedBit(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        parse::<5>("00100\n111101\n"),
+        Err(Day3Error::ExpectedNewline(Position { line: 2, column: 6 }))
+    );
+    assert_eq!(
+        part_1_number_len::<5>("00100\n111"),
+        Err(Day3Error::ExpectedBit(Position { line: 2, column: 4 }))
+    );
+    let long = "1".repeat(65);
+    assert_eq!(
+        part_1_number_len::<65>(&long),
+        Err(Day3Error::TooManyBits(Position { line: 1, column: 1 }))
+    );
+    assert_eq!(
+        parse::<65>(&long),
+        Err(Day3Error::TooManyBits(Position { line: 1, column: 1 }))
+    );
 }
 
 #[test]
@@ -123,7 +312,7 @@ fn test_part_1_example() {
 11001
 00010
 01010";
-    assert_eq!(part_1_number_len::<5>(input), 198);
+    assert_eq!(part_1_number_len::<5>(input), Ok(198));
 }
 
 #[test]
@@ -140,11 +329,57 @@ fn test_part_2_example() {
 11001
 00010
 01010";
-    assert_eq!(part_2_number_len::<5>(input), 230);
+    assert_eq!(part_2_number_len::<5>(input), Ok(230));
+}
+
+#[test]
+fn test_number_len() {
+    assert_eq!(part_1("0\n1\n1"), Ok(0));
+    assert_eq!(part_2("01\n11\n10"), Ok(3));
+    let wide = format!(
+        "1{}\n0{}1\n1{}",
+        "0".repeat(63),
+        "1".repeat(62),
+        "1".repeat(63)
+    );
+    assert_eq!(
+        diagnostics(&wide),
+        Ok(Diagnostics {
+            gamma_rate: u64::MAX,
+            epsilon_rate: 0,
+            oxygen_generator_rating: u64::MAX,
+            co2_scrubber_rating: u64::MAX >> 1,
+        })
+    );
+    assert_eq!(
+        part_2(&wide),
+        Ok(u128::from(u64::MAX) * u128::from(u64::MAX >> 1))
+    );
+    assert_eq!(
+        part_1(&"1".repeat(65)),
+        Err(Day3Error::TooManyBits(Position { line: 1, column: 1 }))
+    );
+    assert_eq!(
+        part_1("0110\n011"),
+        Err(Day3Error::ExpectedBit(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        part_1(""),
+        Err(Day3Error::ExpectedBit(Position { line: 1, column: 1 }))
+    );
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day3.txt");
-    assert_eq!(part_2(input), 4481199);
+fn test_many_lines() {
+    // More lines than fit in a `u16`
+    let input = ["10"; 40_000]
+        .iter()
+        .chain(&["01"; 30_000])
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(
+        diagnostics(&input).map(|d| (d.gamma_rate, d.epsilon_rate)),
+        Ok((0b10, 0b01))
+    );
 }
@@ -1,29 +1,58 @@
-use nom::IResult;
+//! Day 4: Giant Squid. Plays bingo, to find the first and the last card that
+//! wins.
+//!
+//! ```
+//! use advent_of_code_2021::day4;
+//!
+//! let input = "5,4,3,2,1
+//!
+//!  1  2  3  4  5
+//!  6  7  8  9 10
+//! 11 12 13 14 15
+//! 16 17 18 19 20
+//! 21 22 23 24 25";
+//! let (numbers, mut cards) = day4::parse(input).unwrap();
+//! for number in numbers {
+//!     cards[0].mark(number);
+//! }
+//! assert!(cards[0].has_won());
+//! assert_eq!(cards[0].unmarked_number_sum(), 310);
+//! assert_eq!(day4::part_1(input), Ok(310));
+//! ```
 
-pub fn part_1(input: &str) -> u32 {
-    let (_, (numbers, mut cards)) = parse::parse(input.as_bytes())
-        .map_err(|err| panic!("{}", err))
-        .unwrap();
+use nom::{error::ErrorKind, IResult};
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day4Error {
+    #[error("{0}: invalid bingo subsystem input")]
+    Parse(Position),
+    #[error("no bingo card wins")]
+    NoWinningCard,
+}
+
+pub fn part_1(input: &str) -> Result<u32, Day4Error> {
+    let (numbers, mut cards) = parse(input)?;
     for &number in numbers.iter() {
         for card in cards.iter_mut() {
             card.mark(number);
             if card.has_won() {
-                return u32::from(number) * card.unmarked_number_sum();
+                return Ok(u32::from(number) * card.unmarked_number_sum());
             }
         }
     }
-    panic!("No solution!")
+    Err(Day4Error::NoWinningCard)
 }
 
-pub fn part_2(input: &str) -> u32 {
-    let (_, (numbers, mut cards)) = parse::parse(input.as_bytes())
-        .map_err(|err| panic!("{}", err))
-        .unwrap();
+pub fn part_2(input: &str) -> Result<u32, Day4Error> {
+    let (numbers, mut cards) = parse(input)?;
     for &number in numbers.iter() {
         if let [final_card] = cards.as_mut_slice() {
             final_card.mark(number);
             if final_card.has_won() {
-                return u32::from(number) * final_card.unmarked_number_sum();
+                return Ok(u32::from(number) * final_card.unmarked_number_sum());
             }
         } else {
             let mut i = 0;
@@ -38,28 +67,63 @@ pub fn part_2(input: &str) -> u32 {
             }
         }
     }
-    panic!("No solution!")
+    Err(Day4Error::NoWinningCard)
+}
+
+/// Parse the drawn numbers and the bingo cards.
+pub fn parse(input: &str) -> Result<(Vec<u8>, Vec<BingoCard>), Day4Error> {
+    let input = normalize::line_endings(input);
+    parse::parse(input.as_bytes())
+        .map(|(_, parsed)| parsed)
+        .map_err(|e| Day4Error::Parse(Position::of_nom_error(input.as_bytes(), &e)))
 }
 
 const MARKED_MASK: u8 = 0b1000_0000;
 
 #[derive(PartialEq, Eq, Debug)]
-struct BingoCard {
+pub struct BingoCard {
     // Most significant bit is used to mark seen numbers.
     numbers: [u8; 25],
 }
 
 impl BingoCard {
+    /// Parse a card of five rows of five numbers, each right-aligned in a
+    /// column of two characters.
     fn parse(input: &[u8]) -> IResult<&[u8], BingoCard> {
+        const CARD_LEN: usize = 25 * 3 - 1;
+        let error = |offset: usize, kind| {
+            Err(nom::Err::Error(nom::error::Error::new(
+                &input[offset.min(input.len())..],
+                kind,
+            )))
+        };
+        if input.len() < CARD_LEN {
+            return error(input.len(), ErrorKind::Eof);
+        }
         let mut numbers = [0; 25];
-        let (bingo_card_bytes, remainder) = input.split_at(25 * 3 - 1);
-        for (bytes, n) in bingo_card_bytes.chunks(3).zip(numbers.iter_mut()) {
+        let (bingo_card_bytes, remainder) = input.split_at(CARD_LEN);
+        for (i, (bytes, n)) in bingo_card_bytes
+            .chunks(3)
+            .zip(numbers.iter_mut())
+            .enumerate()
+        {
+            if !matches!(bytes[0], b' ' | b'0'..=b'9') {
+                return error(3 * i, ErrorKind::Digit);
+            }
+            if !bytes[1].is_ascii_digit() {
+                return error(3 * i + 1, ErrorKind::Digit);
+            }
+            let separator = if i % 5 == 4 { b'\n' } else { b' ' };
+            if bytes.get(2).is_some_and(|&b| b != separator) {
+                return error(3 * i + 2, ErrorKind::Char);
+            }
             *n = 10 * (bytes[0] & 0b1111) + (bytes[1] & 0b1111);
         }
         Ok((remainder, BingoCard { numbers }))
     }
 
-    fn mark(&mut self, num: u8) {
+    /// Mark the number on the card, if it is on it.
+    pub fn mark(&mut self, num: u8) {
         for number in self.numbers.iter_mut() {
             if *number == num {
                 *number |= MARKED_MASK;
@@ -96,11 +160,13 @@ impl BingoCard {
         false
     }
 
-    fn has_won(&self) -> bool {
+    /// Whether a full row or column is marked.
+    pub fn has_won(&self) -> bool {
         self.has_column() || self.has_row()
     }
 
-    fn unmarked_number_sum(&self) -> u32 {
+    /// The sum of the numbers that are not marked.
+    pub fn unmarked_number_sum(&self) -> u32 {
         self.numbers
             .iter()
             .copied()
@@ -114,7 +180,7 @@ mod parse {
     use nom::{
         bytes::complete::tag,
         character::complete::digit1,
-        combinator::map,
+        combinator::{all_consuming, map},
         multi::separated_list1,
         sequence::{terminated, tuple},
         IResult,
@@ -129,10 +195,10 @@ mod parse {
     }
 
     pub(super) fn parse(input: &[u8]) -> IResult<&[u8], (Vec<u8>, Vec<BingoCard>)> {
-        tuple((
+        all_consuming(tuple((
             terminated(separated_list1(tag(","), number), tag("\n\n")),
             separated_list1(tag("\n\n"), BingoCard::parse),
-        ))(input)
+        )))(input)
     }
 }
 
@@ -183,6 +249,34 @@ fn test_parsing() {
     .unwrap();
 }
 
+#[test]
+fn test_parse_error() {
+    let card = " 1  2  3  4  5
+ 6  7  8  9 10
+11 12 13 14 15
+16 17 18 19 20
+21 22 23 24 25";
+    assert_eq!(
+        parse(&format!("1,2,x\n\n{}", card)),
+        Err(Day4Error::Parse(Position { line: 1, column: 4 }))
+    );
+    assert_eq!(
+        parse(&format!("1,2\n\n{}", card.replace("13", "1x"))),
+        Err(Day4Error::Parse(Position { line: 5, column: 8 }))
+    );
+    assert_eq!(
+        parse(&format!("1,2\n\n{}", &card[..40])),
+        Err(Day4Error::Parse(Position {
+            line: 5,
+            column: 11
+        }))
+    );
+    assert_eq!(
+        part_1(&format!("1,2\n\n{}", card)),
+        Err(Day4Error::NoWinningCard)
+    );
+}
+
 #[test]
 fn test_part_1_example() {
     assert_eq!(
@@ -207,7 +301,7 @@ fn test_part_1_example() {
 22 11 13  6  5
  2  0 12  3  7"
         ),
-        4512
+        Ok(4512)
     );
 }
 
@@ -235,18 +329,6 @@ fn test_part_2_example() {
 22 11 13  6  5
  2  0 12  3  7"
         ),
-        1924
+        Ok(1924)
     );
 }
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day4.txt");
-    assert_eq!(part_1(input), 69579);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day4.txt");
-    assert_eq!(part_2(input), 14877);
-}
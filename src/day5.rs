@@ -1,7 +1,49 @@
+//! Day 5: Hydrothermal Venture. Counts the points where lines of vents
+//! overlap.
+//!
+//! ```
+//! use advent_of_code_2021::day5::{self, Coordinate, LineSegment};
+//!
+//! let input = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4
+//! 6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+//! let segments = day5::parse(input).unwrap();
+//! assert_eq!(
+//!     segments[0],
+//!     LineSegment::from((Coordinate::from((0, 9)), Coordinate::from((5, 9))))
+//! );
+//! assert!(segments[1].is_diagonal());
+//! assert_eq!(segments[3].points().count(), 2);
+//! assert_eq!(day5::part_1(input), Ok(5));
+//! assert_eq!(day5::part_2(input), Ok(12));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+/// The coordinates are in `0..GRID_SIZE`.
+const GRID_SIZE: i16 = 1024;
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day5Error {
+    #[error("{0}: expected a number")]
+    ExpectedNumber(Position),
+    #[error("{0}: coordinates should be less than {}", GRID_SIZE)]
+    CoordinateOutOfRange(Position),
+    #[error("{0}: expected ','")]
+    ExpectedComma(Position),
+    #[error("{0}: expected ' -> '")]
+    ExpectedArrow(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: line segments should be horizontal, vertical or diagonal")]
+    UnsupportedAngle(Position),
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-struct Coordinate {
-    x: i16,
-    y: i16,
+pub struct Coordinate {
+    pub x: i16,
+    pub y: i16,
 }
 
 impl From<(i16, i16)> for Coordinate {
@@ -11,9 +53,9 @@ impl From<(i16, i16)> for Coordinate {
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-struct LineSegment {
-    from: Coordinate,
-    to: Coordinate,
+pub struct LineSegment {
+    pub from: Coordinate,
+    pub to: Coordinate,
 }
 
 impl From<(Coordinate, Coordinate)> for LineSegment {
@@ -23,11 +65,19 @@ impl From<(Coordinate, Coordinate)> for LineSegment {
 }
 
 impl LineSegment {
-    fn is_horizontal_or_vertical(&self) -> bool {
+    pub fn is_horizontal_or_vertical(&self) -> bool {
         self.from.x == self.to.x || self.from.y == self.to.y
     }
 
-    fn points(self) -> impl Iterator<Item = Coordinate> {
+    /// Whether the line is at 45 degrees. Lines of a single point are both
+    /// diagonal and horizontal.
+    pub fn is_diagonal(&self) -> bool {
+        (self.to.x - self.from.x).abs() == (self.to.y - self.from.y).abs()
+    }
+
+    /// The points covered by the line, including both ends. The line should be
+    /// horizontal, vertical or diagonal.
+    pub fn points(self) -> impl Iterator<Item = Coordinate> {
         let len = ((self.to.x - self.from.x).abs() | (self.to.y - self.from.y).abs()) as usize;
         let dx = (self.to.x - self.from.x).signum();
         let dy = (self.to.y - self.from.y).signum();
@@ -42,57 +92,86 @@ impl LineSegment {
 }
 
 mod parse {
-    use super::{Coordinate, LineSegment};
-
-    fn number(input: &[u8]) -> (&[u8], i16) {
-        let (first, mut input) = input.split_first().unwrap();
-        debug_assert!(first.is_ascii_digit());
-        let mut acc = i16::from(first & 0b1111);
-        while let Some((b, new_input)) = input.split_first() {
-            if !b.is_ascii_digit() {
-                break;
+    use super::{Coordinate, Day5Error, LineSegment};
+    use crate::position::Position;
+
+    /// On failure, the remainder of the input at the error and the kind of
+    /// error.
+    type ParseResult<'a, T> = Result<(&'a [u8], T), (&'a [u8], fn(Position) -> Day5Error)>;
+
+    fn number(input: &[u8]) -> ParseResult<'_, i16> {
+        let digits = input.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err((input, Day5Error::ExpectedNumber));
+        }
+        let (number, remainder) = input.split_at(digits);
+        let mut acc: i16 = 0;
+        for b in number {
+            acc = 10 * acc + i16::from(b & 0b1111);
+            if acc >= super::GRID_SIZE {
+                return Err((input, Day5Error::CoordinateOutOfRange));
             }
-            acc *= 10;
-            acc += i16::from(b & 0b1111);
-            input = new_input;
         }
-        (input, acc)
+        Ok((remainder, acc))
     }
 
-    fn coordinate(input: &[u8]) -> (&[u8], Coordinate) {
-        let (input, x) = number(input);
-        let (comma, input) = input.split_first().unwrap();
-        debug_assert_eq!(*comma, b',');
-        let (input, y) = number(input);
-        (input, Coordinate { x, y })
+    fn tag<'a>(
+        input: &'a [u8],
+        tag: &[u8],
+        error: fn(Position) -> Day5Error,
+    ) -> ParseResult<'a, ()> {
+        input
+            .strip_prefix(tag)
+            .map(|input| (input, ()))
+            .ok_or((input, error))
     }
 
-    fn line_segment(input: &[u8]) -> (&[u8], LineSegment) {
-        let (input, from) = coordinate(input);
-        let (arrow, input) = input.split_at(4);
-        debug_assert_eq!(arrow, b" -> ");
-        let (input, to) = coordinate(input);
-        (input, LineSegment::from((from, to)))
+    fn coordinate(input: &[u8]) -> ParseResult<'_, Coordinate> {
+        let (input, x) = number(input)?;
+        let (input, ()) = tag(input, b",", Day5Error::ExpectedComma)?;
+        let (input, y) = number(input)?;
+        Ok((input, Coordinate { x, y }))
     }
 
-    pub(super) fn entire_input(input: &[u8]) -> impl Iterator<Item = LineSegment> + '_ {
-        let (input, segment) = line_segment(input);
-        std::iter::successors(Some((input, segment)), |(mut input, _segment)| {
-            if let Some((newline, new_input)) = input.split_first() {
-                debug_assert_eq!(*newline, b'\n');
-                input = new_input;
-                Some(line_segment(input))
-            } else {
-                None
+    fn line_segment(input: &[u8]) -> ParseResult<'_, LineSegment> {
+        let start = input;
+        let (input, from) = coordinate(input)?;
+        let (input, ()) = tag(input, b" -> ", Day5Error::ExpectedArrow)?;
+        let (input, to) = coordinate(input)?;
+        let segment = LineSegment::from((from, to));
+        if !segment.is_horizontal_or_vertical() && !segment.is_diagonal() {
+            return Err((start, Day5Error::UnsupportedAngle));
+        }
+        Ok((input, segment))
+    }
+
+    pub(super) fn entire_input(input: &[u8]) -> ParseResult<'_, Vec<LineSegment>> {
+        let mut segments = Vec::new();
+        let mut remainder = input;
+        loop {
+            let (input, segment) = line_segment(remainder)?;
+            segments.push(segment);
+            if input.is_empty() {
+                return Ok((input, segments));
             }
-        })
-        .map(|(_, segment)| segment)
+            let (input, ()) = tag(input, b"\n", Day5Error::ExpectedNewline)?;
+            remainder = input;
+        }
     }
 }
 
-pub fn part_1(input: &str) -> usize {
-    let mut seen = vec![u8::MAX - 1; 1024 * 1024];
+/// Parse the line segments of the hydrothermal vents.
+pub fn parse(input: &str) -> Result<Vec<LineSegment>, Day5Error> {
+    let input = normalize::line_endings(input);
     parse::entire_input(input.as_bytes())
+        .map(|(_, segments)| segments)
+        .map_err(|(remainder, error)| error(Position::of_remainder(input.as_bytes(), remainder)))
+}
+
+pub fn part_1(input: &str) -> Result<usize, Day5Error> {
+    let mut seen = vec![u8::MAX - 1; 1024 * 1024];
+    Ok(parse(input)?
+        .into_iter()
         .filter(LineSegment::is_horizontal_or_vertical)
         .flat_map(LineSegment::points)
         .map(|Coordinate { x, y }| (x as usize) << 10 | (y as usize))
@@ -100,19 +179,57 @@ pub fn part_1(input: &str) -> usize {
             seen[index] = seen[index].wrapping_add(1);
             seen[index] == 0
         })
-        .count()
+        .count())
 }
 
-pub fn part_2(input: &str) -> usize {
+pub fn part_2(input: &str) -> Result<usize, Day5Error> {
     let mut seen = vec![u8::MAX - 1; 1024 * 1024];
-    parse::entire_input(input.as_bytes())
+    Ok(parse(input)?
+        .into_iter()
         .flat_map(LineSegment::points)
         .map(|Coordinate { x, y }| (x as usize) << 10 | (y as usize))
         .filter(|&index| {
             seen[index] = seen[index].wrapping_add(1);
             seen[index] == 0
         })
-        .count()
+        .count())
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        parse("0,9 -> 5,9\n8,0 - 0,8"),
+        Err(Day5Error::ExpectedArrow(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        parse("0,9 -> 5,9\n8;0 -> 0,8"),
+        Err(Day5Error::ExpectedComma(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse("0,9 -> 5,9 \n"),
+        Err(Day5Error::ExpectedNewline(Position {
+            line: 1,
+            column: 11
+        }))
+    );
+    assert_eq!(
+        parse("0,9 -> 5,\n"),
+        Err(Day5Error::ExpectedNumber(Position {
+            line: 1,
+            column: 10
+        }))
+    );
+    assert_eq!(
+        parse("0,9 -> 1024,9"),
+        Err(Day5Error::CoordinateOutOfRange(Position {
+            line: 1,
+            column: 8
+        }))
+    );
+    assert_eq!(
+        parse("0,0 -> 1,2"),
+        Err(Day5Error::UnsupportedAngle(Position { line: 1, column: 1 }))
+    );
 }
 
 #[test]
@@ -127,7 +244,7 @@ fn test_part_1_example() {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2";
-    assert_eq!(part_1(input), 5);
+    assert_eq!(part_1(input), Ok(5));
 }
 
 #[test]
@@ -142,17 +259,5 @@ fn test_part_2_example() {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2";
-    assert_eq!(part_2(input), 12);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day5.txt");
-    assert_eq!(part_1(input), 7674);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day5.txt");
-    assert_eq!(part_2(input), 20898);
+    assert_eq!(part_2(input), Ok(12));
 }
@@ -1,193 +1,112 @@
-use std::fmt::Debug;
-
-#[derive(Copy, Clone, PartialEq, Eq)]
-struct Matrix<const HEIGHT: usize, const WIDTH: usize> {
-    m: [[usize; WIDTH]; HEIGHT],
-}
-
-impl<const I: usize, const K: usize> Debug for Matrix<I, K> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in 0..I {
-            write!(f, "[")?;
-            for k in 0..K {
-                if k != 0 {
-                    write!(f, "\t")?;
-                }
-                write!(f, "{}", self.m[i][k])?;
-            }
-            writeln!(f, "]")?;
-        }
-        Ok(())
-    }
+//! Day 6: Lanternfish. Counts the fish after 80 and 256 days.
+//!
+//! ```
+//! use advent_of_code_2021::day6;
+//!
+//! assert_eq!(day6::part_1("3,4,3,1,2"), Ok(5934));
+//! assert_eq!(day6::part_2("3,4,3,1,2"), Ok(26984457539));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day6Error {
+    #[error("{0}: expected a timer between 0 and 8")]
+    ExpectedTimer(Position),
+    #[error("{0}: expected ','")]
+    ExpectedComma(Position),
 }
 
-impl<const H: usize> Matrix<H, 1> {
-    const fn single_value(f: u8) -> Self {
-        let mut m = [[0]; H];
-        m[(f as usize) % H][0] = 1;
-        Matrix { m }
-    }
-}
+#[cfg(test)]
+mod matrix;
 
-impl<const I: usize, const K: usize> Matrix<I, K> {
-    const fn mul<const J: usize>(&self, other: Matrix<K, J>) -> Matrix<I, J> {
-        let mut m: Matrix<I, J> = Matrix { m: [[0; J]; I] };
-        let mut i = I;
-        loop {
-            i -= 1;
-            let mut j = J;
-            loop {
-                j -= 1;
-                let mut k = K;
-                loop {
-                    k -= 1;
-                    m.m[i][j] += self.m[i][k] * other.m[k][j];
-                    if k == 0 {
-                        break;
-                    }
-                }
-                if j == 0 {
-                    break;
-                }
-            }
-            if i == 0 {
-                break;
-            }
-        }
-        m
-    }
-}
+const BUFFER_LENGTH: usize = 9;
 
-impl<const I: usize> Matrix<I, I> {
-    const fn exp(&self, mut e: usize) -> Matrix<I, I> {
-        let mut result = *self;
-        loop {
-            e -= 1;
-            if e == 0 {
-                return result;
-            }
-            result = result.mul(*self);
+// The number of fish a single fish becomes after 80 and 256 days, by the ASCII
+// digit of its timer modulo `BUFFER_LENGTH`: `MATRIX_80` and `MATRIX_256`.
+// They are computed by the build script, using the matrices in `matrix.rs`.
+include!(concat!(env!("OUT_DIR"), "/day6_tables.rs"));
+
+/// Yields the timers as the ASCII digits in the input.
+fn timers(input: &[u8]) -> impl Iterator<Item = Result<u8, Day6Error>> + '_ {
+    input.chunks(2).enumerate().map(move |(i, chunk)| {
+        let error_at = |offset| Position::of(input, 2 * i + offset);
+        match chunk {
+            [_, b','] if 2 * i + 2 == input.len() => Err(Day6Error::ExpectedTimer(error_at(2))),
+            [f @ b'0'..=b'8'] | [f @ b'0'..=b'8', b','] => Ok(*f),
+            [b'0'..=b'8', _] => Err(Day6Error::ExpectedComma(error_at(1))),
+            _ => Err(Day6Error::ExpectedTimer(error_at(0))),
         }
-    }
+    })
 }
 
-impl Matrix<1, 1> {
-    const fn val(&self) -> usize {
-        self.m[0][0]
-    }
+/// Parse the internal timers of the lanternfish.
+pub fn parse(input: &str) -> Result<Vec<u8>, Day6Error> {
+    let input = normalize::line_endings(input);
+    timers(input.as_bytes())
+        .map(|f| f.map(|f| f - b'0'))
+        .collect()
 }
 
-const MATRIX_ONE: Matrix<BUFFER_LENGTH, BUFFER_LENGTH> = Matrix {
-    m: [
-        [0, 1, 0, 0, 0, 0, 0, 0, 0],
-        [0, 0, 1, 0, 0, 0, 0, 0, 0],
-        [0, 0, 0, 1, 0, 0, 0, 0, 0],
-        [0, 0, 0, 0, 1, 0, 0, 0, 0],
-        [0, 0, 0, 0, 0, 1, 0, 0, 0],
-        [0, 0, 0, 0, 0, 0, 1, 0, 0],
-        [1, 0, 0, 0, 0, 0, 0, 1, 0],
-        [0, 0, 0, 0, 0, 0, 0, 0, 1],
-        [1, 0, 0, 0, 0, 0, 0, 0, 0],
-    ],
-};
-
-const BUFFER_LENGTH: usize = 9;
-const SUM_MATRIX: Matrix<1, BUFFER_LENGTH> = Matrix {
-    m: [[1; BUFFER_LENGTH]],
-};
-/// In order to avoid a subtraction when parsing, don't subtract b'0'.
-/// Instead, use a transposition matrix, which can be compiled into the multiplication.
-const TRANSPOSITION: Matrix<BUFFER_LENGTH, BUFFER_LENGTH> = Matrix {
-    m: [
-        [0, 0, 0, 1, 0, 0, 0, 0, 0],
-        [0, 0, 0, 0, 1, 0, 0, 0, 0],
-        [0, 0, 0, 0, 0, 1, 0, 0, 0],
-        [0, 0, 0, 0, 0, 0, 1, 0, 0],
-        [0, 0, 0, 0, 0, 0, 0, 1, 0],
-        [0, 0, 0, 0, 0, 0, 0, 0, 1],
-        [1, 0, 0, 0, 0, 0, 0, 0, 0],
-        [0, 1, 0, 0, 0, 0, 0, 0, 0],
-        [0, 0, 1, 0, 0, 0, 0, 0, 0],
-    ],
-};
-const MATRIX_80: Matrix<1, BUFFER_LENGTH> = SUM_MATRIX.mul(MATRIX_ONE.exp(80).mul(TRANSPOSITION));
-const MATRIX_256: Matrix<1, BUFFER_LENGTH> = SUM_MATRIX.mul(MATRIX_ONE.exp(256).mul(TRANSPOSITION));
-
-const VALUE_80_FOR_0: usize = MATRIX_80.mul(Matrix::single_value(b'0')).val();
-const VALUE_80_FOR_1: usize = MATRIX_80.mul(Matrix::single_value(b'1')).val();
-const VALUE_80_FOR_2: usize = MATRIX_80.mul(Matrix::single_value(b'2')).val();
-const VALUE_80_FOR_3: usize = MATRIX_80.mul(Matrix::single_value(b'3')).val();
-const VALUE_80_FOR_4: usize = MATRIX_80.mul(Matrix::single_value(b'4')).val();
-const VALUE_80_FOR_5: usize = MATRIX_80.mul(Matrix::single_value(b'5')).val();
-
-const VALUE_256_FOR_0: usize = MATRIX_256.mul(Matrix::single_value(b'0')).val();
-const VALUE_256_FOR_1: usize = MATRIX_256.mul(Matrix::single_value(b'1')).val();
-const VALUE_256_FOR_2: usize = MATRIX_256.mul(Matrix::single_value(b'2')).val();
-const VALUE_256_FOR_3: usize = MATRIX_256.mul(Matrix::single_value(b'3')).val();
-const VALUE_256_FOR_4: usize = MATRIX_256.mul(Matrix::single_value(b'4')).val();
-const VALUE_256_FOR_5: usize = MATRIX_256.mul(Matrix::single_value(b'5')).val();
-
-fn parse(input: &[u8]) -> impl Iterator<Item = u8> + '_ {
-    input.iter().step_by(2).copied()
-}
+#[cfg(test)]
+use matrix::{Matrix, MATRIX_ONE, TRANSPOSITION};
 
 #[cfg(test)]
 fn matrix_from_input(input: &[u8]) -> Matrix<BUFFER_LENGTH, 1> {
     let mut m = [[0]; BUFFER_LENGTH];
-    for f in parse(input) {
-        m[usize::from(f) % BUFFER_LENGTH][0] += 1;
+    for f in timers(input) {
+        m[usize::from(f.unwrap()) % BUFFER_LENGTH][0] += 1;
     }
     Matrix { m }
 }
 
-pub fn part_1(input: &str) -> usize {
-    parse(input.as_bytes())
-        .map(|f| match f {
-            b'0' => VALUE_80_FOR_0,
-            b'1' => VALUE_80_FOR_1,
-            b'2' => VALUE_80_FOR_2,
-            b'3' => VALUE_80_FOR_3,
-            b'4' => VALUE_80_FOR_4,
-            _ => VALUE_80_FOR_5,
-        })
+pub fn part_1(input: &str) -> Result<usize, Day6Error> {
+    let input = normalize::line_endings(input);
+    timers(input.as_bytes())
+        .map(|f| f.map(|f| MATRIX_80[usize::from(f) % BUFFER_LENGTH]))
         .sum()
 }
 
-pub fn part_2(input: &str) -> usize {
-    parse(input.as_bytes())
-        .map(|f| match f {
-            b'0' => VALUE_256_FOR_0,
-            b'1' => VALUE_256_FOR_1,
-            b'2' => VALUE_256_FOR_2,
-            b'3' => VALUE_256_FOR_3,
-            b'4' => VALUE_256_FOR_4,
-            _ => VALUE_256_FOR_5,
-        })
+pub fn part_2(input: &str) -> Result<usize, Day6Error> {
+    let input = normalize::line_endings(input);
+    timers(input.as_bytes())
+        .map(|f| f.map(|f| MATRIX_256[usize::from(f) % BUFFER_LENGTH]))
         .sum()
 }
 
 #[test]
-fn test_example_part_1() {
-    let input = "3,4,3,1,2";
-    assert_eq!(part_1(input), 5934);
+fn test_parse_error() {
+    assert_eq!(parse("3,4,8,0"), Ok(vec![3, 4, 8, 0]));
+    assert_eq!(
+        parse("3,4,9"),
+        Err(Day6Error::ExpectedTimer(Position { line: 1, column: 5 }))
+    );
+    assert_eq!(
+        parse("3,4;3"),
+        Err(Day6Error::ExpectedComma(Position { line: 1, column: 4 }))
+    );
+    assert_eq!(
+        parse("3,4,"),
+        Err(Day6Error::ExpectedTimer(Position { line: 1, column: 5 }))
+    );
+    assert_eq!(
+        part_1("3,45"),
+        Err(Day6Error::ExpectedComma(Position { line: 1, column: 4 }))
+    );
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day6.txt");
-    assert_eq!(part_1(input), 372300);
+fn test_example_part_1() {
+    let input = "3,4,3,1,2";
+    assert_eq!(part_1(input), Ok(5934));
 }
 
 #[test]
 fn test_example_part_2() {
     let input = "3,4,3,1,2";
-    assert_eq!(part_2(input), 26984457539);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day6.txt");
-    assert_eq!(part_2(input), 1675781200288);
+    assert_eq!(part_2(input), Ok(26984457539));
 }
 
 #[test]
@@ -211,3 +130,9 @@ fn tests_with_matrices() {
         matrix("6,0,6,4,5,6,7,8,8")
     );
 }
+
+#[test]
+fn test_tables() {
+    assert_eq!(MATRIX_80, matrix::fish_after(80));
+    assert_eq!(MATRIX_256, matrix::fish_after(256));
+}
@@ -0,0 +1,108 @@
+//! The matrices that step the counts of lanternfish per timer forward by a
+//! day. This file is also included by the build script, which uses it to
+//! compute the tables in `day6.rs`.
+
+use std::fmt::Debug;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Matrix<const HEIGHT: usize, const WIDTH: usize> {
+    pub m: [[usize; WIDTH]; HEIGHT],
+}
+
+impl<const I: usize, const K: usize> Debug for Matrix<I, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in 0..I {
+            write!(f, "[")?;
+            for k in 0..K {
+                if k != 0 {
+                    write!(f, "\t")?;
+                }
+                write!(f, "{}", self.m[i][k])?;
+            }
+            writeln!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+impl<const I: usize, const K: usize> Matrix<I, K> {
+    pub const fn mul<const J: usize>(&self, other: Matrix<K, J>) -> Matrix<I, J> {
+        let mut m: Matrix<I, J> = Matrix { m: [[0; J]; I] };
+        let mut i = I;
+        loop {
+            i -= 1;
+            let mut j = J;
+            loop {
+                j -= 1;
+                let mut k = K;
+                loop {
+                    k -= 1;
+                    m.m[i][j] += self.m[i][k] * other.m[k][j];
+                    if k == 0 {
+                        break;
+                    }
+                }
+                if j == 0 {
+                    break;
+                }
+            }
+            if i == 0 {
+                break;
+            }
+        }
+        m
+    }
+}
+
+impl<const I: usize> Matrix<I, I> {
+    pub const fn exp(&self, mut e: usize) -> Matrix<I, I> {
+        let mut result = *self;
+        loop {
+            e -= 1;
+            if e == 0 {
+                return result;
+            }
+            result = result.mul(*self);
+        }
+    }
+}
+
+pub const MATRIX_ONE: Matrix<BUFFER_LENGTH, BUFFER_LENGTH> = Matrix {
+    m: [
+        [0, 1, 0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 1, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 1, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 1, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 1, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 1, 0, 0],
+        [1, 0, 0, 0, 0, 0, 0, 1, 0],
+        [0, 0, 0, 0, 0, 0, 0, 0, 1],
+        [1, 0, 0, 0, 0, 0, 0, 0, 0],
+    ],
+};
+
+pub const BUFFER_LENGTH: usize = 9;
+pub const SUM_MATRIX: Matrix<1, BUFFER_LENGTH> = Matrix {
+    m: [[1; BUFFER_LENGTH]],
+};
+/// In order to avoid a subtraction when parsing, don't subtract b'0'.
+/// Instead, use a transposition matrix, which can be compiled into the multiplication.
+pub const TRANSPOSITION: Matrix<BUFFER_LENGTH, BUFFER_LENGTH> = Matrix {
+    m: [
+        [0, 0, 0, 1, 0, 0, 0, 0, 0],
+        [0, 0, 0, 0, 1, 0, 0, 0, 0],
+        [0, 0, 0, 0, 0, 1, 0, 0, 0],
+        [0, 0, 0, 0, 0, 0, 1, 0, 0],
+        [0, 0, 0, 0, 0, 0, 0, 1, 0],
+        [0, 0, 0, 0, 0, 0, 0, 0, 1],
+        [1, 0, 0, 0, 0, 0, 0, 0, 0],
+        [0, 1, 0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 1, 0, 0, 0, 0, 0, 0],
+    ],
+};
+
+/// The number of fish that a single fish becomes after `days` days, by the
+/// ASCII digit of its timer modulo `BUFFER_LENGTH`.
+pub fn fish_after(days: usize) -> [usize; BUFFER_LENGTH] {
+    SUM_MATRIX.mul(MATRIX_ONE.exp(days).mul(TRANSPOSITION)).m[0]
+}
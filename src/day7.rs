@@ -1,68 +1,109 @@
-use std::cmp::Ordering;
-
-fn parse_numbers(input: &str) -> impl Iterator<Item = u16> + '_ {
-    let mut iter = input
-        .as_bytes()
-        .iter()
-        .copied()
-        .inspect(|&a| debug_assert!(a.is_ascii_digit() || a == b','));
-    std::iter::from_fn(move || {
-        iter.next().map(|x| {
-            (&mut iter)
-                .take_while(|b| *b != b',')
-                .fold(u16::from(x & 0b1111), |acc, digit| {
-                    10 * acc + u16::from(digit & 0b1111)
+//! Day 7: The Treachery of Whales. Finds the cheapest position to align the
+//! crabs on.
+//!
+//! ```
+//! use advent_of_code_2021::day7;
+//!
+//! let input = "16,1,2,0,4,2,7,1,2,14";
+//! assert_eq!(day7::part_1(input), Ok(37));
+//! assert_eq!(day7::part_2_mean(input), Ok(168));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day7Error {
+    #[error("{0}: expected a number")]
+    ExpectedNumber(Position),
+    #[error("{0}: number is too large")]
+    NumberTooLarge(Position),
+    #[error("there are no crabs")]
+    NoCrabs,
+}
+
+/// Yields the numbers in the input, together with their offsets.
+fn numbers(input: &[u8]) -> impl Iterator<Item = Result<(usize, u16), Day7Error>> + '_ {
+    let mut start = 0;
+    input
+        .split(|&b| b == b',')
+        .take_while(move |_| !input.is_empty())
+        .map(move |number| {
+            let offset = start;
+            start += number.len() + 1;
+            if let Some(i) = number.iter().position(|b| !b.is_ascii_digit()) {
+                return Err(Day7Error::ExpectedNumber(Position::of(input, offset + i)));
+            }
+            if number.is_empty() {
+                return Err(Day7Error::ExpectedNumber(Position::of(input, offset)));
+            }
+            number
+                .iter()
+                .try_fold(0u16, |acc, digit| {
+                    acc.checked_mul(10)?.checked_add(u16::from(digit & 0b1111))
                 })
+                .map(|value| (offset, value))
+                .ok_or_else(|| Day7Error::NumberTooLarge(Position::of(input, offset)))
         })
-    })
+}
+
+/// Parse the horizontal positions of the crabs.
+pub fn parse(input: &str) -> Result<Vec<u16>, Day7Error> {
+    let input = normalize::line_endings(input);
+    numbers(input.as_bytes())
+        .map(|number| number.map(|(_, value)| value))
+        .collect()
 }
 
 /// Find the k'th item if `input` would be sorted, even if it isn't.
 fn k_th(input: &mut [u16], k: usize) -> u16 {
     debug_assert!(!input.is_empty());
-    let pivot = input[0];
-    let remaining_input = &mut input[1..];
-    let order_of_pivot = remaining_input
-        .iter_mut()
-        .partition_in_place(|item| *item < pivot);
-    match k.cmp(&order_of_pivot) {
-        Ordering::Greater => k_th(
-            &mut remaining_input[order_of_pivot..],
-            k - 1 - order_of_pivot,
-        ),
-        Ordering::Equal => pivot,
-        Ordering::Less => k_th(&mut remaining_input[..order_of_pivot], k),
-    }
+    *input.select_nth_unstable(k).1
 }
 
-pub fn part_1(input: &str) -> usize {
-    let mut numbers: Vec<_> = parse_numbers(input).collect();
+pub fn part_1(input: &str) -> Result<usize, Day7Error> {
+    let mut numbers = parse(input)?;
+    if numbers.is_empty() {
+        return Err(Day7Error::NoCrabs);
+    }
     let mid_point = numbers.len() / 2;
     let mid_point_value = k_th(&mut numbers, mid_point);
     // As a result of computing the kth point, the array is actually partitioned in < k, > k.
     // However, the naive way is faster
-    numbers
+    Ok(numbers
         .into_iter()
         .map(|x| usize::from(x.abs_diff(mid_point_value)))
-        .sum()
+        .sum())
 }
 
-pub fn part_2_incrementing_mu(input: &str) -> usize {
+pub fn part_2_incrementing_mu(input: &str) -> Result<usize, Day7Error> {
     part_2_incrementing_mu_sized::<2000>(input)
 }
 
-fn part_2_incrementing_mu_sized<const MAX_VALUE: usize>(input: &str) -> usize {
+fn part_2_incrementing_mu_sized<const MAX_VALUE: usize>(input: &str) -> Result<usize, Day7Error> {
+    let input = normalize::line_endings(input);
     let mut frequency_table = vec![0; MAX_VALUE];
     let mut count = 0;
     let mut sum = 0;
     let mut sum_of_squares = 0;
-    for number in parse_numbers(input).map(usize::from) {
-        debug_assert!(number < MAX_VALUE, "number exceeds maximum value - 1!");
+    for number in numbers(input.as_bytes()) {
+        let (offset, number) = number?;
+        let number = usize::from(number);
+        if number >= MAX_VALUE {
+            return Err(Day7Error::NumberTooLarge(Position::of(
+                input.as_bytes(),
+                offset,
+            )));
+        }
         frequency_table[number] += 1;
         count += 1;
         sum += number;
         sum_of_squares += number * number;
     }
+    if count == 0 {
+        return Err(Day7Error::NoCrabs);
+    }
     // For mu = 0
     let mut numbers_less_than_eq_mu = 0;
     let mut fuel_at_mu = (sum + sum_of_squares) / 2;
@@ -87,77 +128,89 @@ fn part_2_incrementing_mu_sized<const MAX_VALUE: usize>(input: &str) -> usize {
         fuel_at_mu += fuel_increase;
         fuel_at_mu -= fuel_decrease;
     }
-    fuel_at_mu
+    Ok(fuel_at_mu)
 }
 
-pub fn part_2_mean(input: &str) -> usize {
+pub fn part_2_mean(input: &str) -> Result<usize, Day7Error> {
     // Actually, the mean or mean + 1 is the right solution
     // This answer is pretty much stolen from here:
     // https://github.com/aldanor/aoc-2021/blob/master/src/day07/mod.rs
-    let mut numbers: Vec<_> = Vec::with_capacity(input.len() / 2);
-    numbers.extend(parse_numbers(input).map(usize::from));
-    let mean = numbers.iter().copied().sum::<usize>() / numbers.len();
-    let (fuel_1, fuel_2) = numbers.into_iter().fold((0, 0), |(fuel_1, fuel_2), x| {
+    let numbers = parse(input)?;
+    if numbers.is_empty() {
+        return Err(Day7Error::NoCrabs);
+    }
+    let numbers = numbers.into_iter().map(usize::from);
+    let mean = numbers.clone().sum::<usize>() / numbers.len();
+    let (fuel_1, fuel_2) = numbers.fold((0, 0), |(fuel_1, fuel_2), x| {
         let d1 = x.abs_diff(mean);
         let d2 = x.abs_diff(mean + 1);
         (fuel_1 + d1 * (d1 + 1), fuel_2 + d2 * (d2 + 1))
     });
-    fuel_1.min(fuel_2) / 2
+    Ok(fuel_1.min(fuel_2) / 2)
 }
 
 #[test]
 fn test_part_1_example_input() {
     let input = "16,1,2,0,4,2,7,1,2,14";
-    assert_eq!(part_1(input), 37);
+    assert_eq!(part_1(input), Ok(37));
 }
 
 #[test]
 fn test_part_1_duplicate_midpoint() {
     let input = "0,1,1,1,1,1,1,1,2,3";
-    assert_eq!(part_1(input), 4);
+    assert_eq!(part_1(input), Ok(4));
 }
 
 #[test]
 fn test_odd_even() {
     // Odd and even should both work, so care should be taken with the middle value
-    assert_eq!(part_1("0,1,2"), 2);
-    assert_eq!(part_1("0,1,1,2"), 2);
+    assert_eq!(part_1("0,1,2"), Ok(2));
+    assert_eq!(part_1("0,1,1,2"), Ok(2));
 }
 
 #[test]
 fn test_part_2_example_input() {
     let input = "16,1,2,0,4,2,7,1,2,14";
-    assert_eq!(part_2_incrementing_mu_sized::<20>(input), 168);
+    assert_eq!(part_2_incrementing_mu_sized::<20>(input), Ok(168));
 }
 
 #[test]
 fn test_part_2_mean_example_input() {
     let input = "16,1,2,0,4,2,7,1,2,14";
-    assert_eq!(part_2_mean(input), 168);
+    assert_eq!(part_2_mean(input), Ok(168));
 }
 
 #[test]
-fn test_kth() {
-    assert_eq!(k_th(&mut vec![2, 0, 1], 0), 0);
-    assert_eq!(k_th(&mut vec![2, 0, 1], 1), 1);
-    assert_eq!(k_th(&mut vec![2, 0, 1], 2), 2);
-    assert_eq!(k_th(&mut vec![0, 5, 1, 2, 3, 6, 4], 3), 3)
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day7.txt");
-    assert_eq!(part_1(input), 339321);
+fn test_parse_error() {
+    assert_eq!(parse("16,1,2"), Ok(vec![16, 1, 2]));
+    assert_eq!(
+        parse("16,1,,2"),
+        Err(Day7Error::ExpectedNumber(Position { line: 1, column: 6 }))
+    );
+    assert_eq!(
+        parse("16,1a,2"),
+        Err(Day7Error::ExpectedNumber(Position { line: 1, column: 5 }))
+    );
+    assert_eq!(
+        parse("16,70000"),
+        Err(Day7Error::NumberTooLarge(Position { line: 1, column: 4 }))
+    );
+    assert_eq!(
+        part_2_incrementing_mu_sized::<20>("16,1,20"),
+        Err(Day7Error::NumberTooLarge(Position { line: 1, column: 6 }))
+    );
+    assert_eq!(part_1(""), Err(Day7Error::NoCrabs));
+    assert_eq!(
+        part_2_incrementing_mu_sized::<20>(""),
+        Err(Day7Error::NoCrabs)
+    );
+    assert_eq!(part_2_mean(""), Err(Day7Error::NoCrabs));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day7.txt");
-    assert_eq!(part_2_incrementing_mu(input), 95476244);
-}
-
-#[test]
-fn test_part_2_mean_input() {
-    let input = include_str!("../input/2021/day7.txt");
-    assert_eq!(part_2_mean(input), 95476244);
+fn test_kth() {
+    assert_eq!(k_th(&mut [2, 0, 1], 0), 0);
+    assert_eq!(k_th(&mut [2, 0, 1], 1), 1);
+    assert_eq!(k_th(&mut [2, 0, 1], 2), 2);
+    assert_eq!(k_th(&mut [0, 5, 1, 2, 3, 6, 4], 3), 3)
 }
@@ -1,46 +1,108 @@
-fn parse_digit(input: &mut &[u8]) -> Option<u8> {
-    if let Some((&first, remaining)) = input.split_first() {
-        *input = remaining;
-        match first {
-            b'a' => Some(0b1),
-            b'b' => Some(0b10),
-            b'c' => Some(0b100),
-            b'd' => Some(0b1000),
-            b'e' => Some(0b10000),
-            b'f' => Some(0b100000),
-            b'g' => Some(0b10000000),
-            _ => None,
-        }
-    } else {
-        None
-    }
+//! Day 8: Seven Segment Search. Works out the wiring of the displays from the
+//! signal patterns.
+//!
+//! ```
+//! use advent_of_code_2021::day8;
+//!
+//! let input = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+//! assert_eq!(day8::part_1(input), Ok(0));
+//! assert_eq!(day8::part_2(input), Ok(5353));
+//! ```
+
+use thiserror::Error;
+
+use crate::{normalize, position::Position};
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day8Error {
+    #[error("{0}: expected a signal pattern of segments a to g")]
+    ExpectedPattern(Position),
+    #[error("{0}: expected '|'")]
+    ExpectedDelimiter(Position),
+    #[error("{0}: the signal patterns in this entry can not be decoded")]
+    UnknownDigit(Position),
 }
+
+/// Parse a signal pattern, followed by a space, a newline or the end of the
+/// input. On error, `input` points at the unexpected byte.
 fn parse_segment(input: &mut &[u8]) -> Option<u8> {
-    parse_digit(input)
-        .map(|first| std::iter::from_fn(|| parse_digit(input)).fold(first, |acc, d| acc | d))
+    let len = input
+        .iter()
+        .take_while(|b| matches!(b, b'a'..=b'g'))
+        .count();
+    if len == 0 || !matches!(input.get(len), None | Some(b' ' | b'\n')) {
+        *input = &input[len..];
+        return None;
+    }
+    let segment = input[..len].iter().fold(0, |acc, b| acc | 1 << (b - b'a'));
+    *input = &input[(len + 1).min(input.len())..];
+    Some(segment)
 }
 
-fn parse_multiple<const COUNT: usize>(input: &mut &[u8]) -> [u8; COUNT] {
+fn parse_multiple<const COUNT: usize>(input: &mut &[u8]) -> Option<[u8; COUNT]> {
     let mut digits = [0; COUNT];
     for digit_dest in digits.iter_mut() {
-        *digit_dest = parse_segment(input).unwrap();
+        *digit_dest = parse_segment(input)?;
     }
-    digits
+    Some(digits)
+}
+
+/// The ten unique signal patterns and the four digit output value. Each
+/// pattern is a bitmask of the segments a to g.
+pub type Entry = ([u8; 10], [u8; 4]);
+
+/// Yields the entries in the input, together with the offset at which they
+/// start.
+fn entries(input: &[u8]) -> impl Iterator<Item = Result<(usize, Entry), Day8Error>> + '_ {
+    let mut remainder = input;
+    std::iter::from_fn(move || {
+        if remainder.is_empty() {
+            return None;
+        }
+        let start = input.len() - remainder.len();
+        let mut parse_line = || {
+            let error = |remainder, error: fn(Position) -> Day8Error| {
+                error(Position::of_remainder(input, remainder))
+            };
+            let digits = parse_multiple::<10>(&mut remainder)
+                .ok_or_else(|| error(remainder, Day8Error::ExpectedPattern))?;
+            // The delimiter may be followed by a newline in the examples
+            if !matches!(remainder, [b'|', b' ' | b'\n', ..]) {
+                return Err(error(remainder, Day8Error::ExpectedDelimiter));
+            }
+            remainder = &remainder[2..];
+            let display = parse_multiple::<4>(&mut remainder)
+                .ok_or_else(|| error(remainder, Day8Error::ExpectedPattern))?;
+            Ok((start, (digits, display)))
+        };
+        let entry = parse_line();
+        if entry.is_err() {
+            // Stop after the first error
+            remainder = &[];
+        }
+        Some(entry)
+    })
 }
 
-fn parse_line(input: &mut &[u8]) -> ([u8; 10], [u8; 4]) {
-    let digits = parse_multiple::<10>(input);
-    *input = &input[2..];
-    let remainder = parse_multiple::<4>(input);
-    (digits, remainder)
+/// Parse the entries in the input.
+pub fn parse(input: &str) -> Result<Vec<Entry>, Day8Error> {
+    let input = normalize::line_endings(input);
+    entries(input.as_bytes())
+        .map(|entry| entry.map(|(_, entry)| entry))
+        .collect()
 }
 
-pub fn part_1(input: &str) -> usize {
-    let mut input = input.as_bytes();
-    std::iter::from_fn(|| Some(&mut input).filter(|i| !i.is_empty()).map(parse_line))
-        .flat_map(|(_, display)| display.into_iter())
-        .filter(|digit| matches!(digit.count_ones(), 2 | 4 | 3 | 7))
-        .count()
+pub fn part_1(input: &str) -> Result<usize, Day8Error> {
+    let input = normalize::line_endings(input);
+    let mut count = 0;
+    for entry in entries(input.as_bytes()) {
+        let (_, (_, display)) = entry?;
+        count += display
+            .into_iter()
+            .filter(|digit| matches!(digit.count_ones(), 2 | 4 | 3 | 7))
+            .count();
+    }
+    Ok(count)
 }
 
 fn find_one_and_four(digits: [u8; 10]) -> (u8, u8) {
@@ -59,12 +121,12 @@ fn find_one_and_four(digits: [u8; 10]) -> (u8, u8) {
 struct Decoder([u8; 10]);
 
 impl Decoder {
-    fn from_digits(digits: [u8; 10]) -> Decoder {
+    fn from_digits(digits: [u8; 10]) -> Option<Decoder> {
         let (one, four) = find_one_and_four(digits);
-        Decoder(Decoder::find_encodings(digits, one, four))
+        Decoder::find_encodings(digits, one, four).map(Decoder)
     }
 
-    fn find_encodings(digits: [u8; 10], one_encoding: u8, four_encoding: u8) -> [u8; 10] {
+    fn find_encodings(digits: [u8; 10], one_encoding: u8, four_encoding: u8) -> Option<[u8; 10]> {
         let mut encodings = [0; 10];
         for digit in digits {
             let ones = digit.count_ones();
@@ -81,28 +143,33 @@ impl Decoder {
                 (3, _, _) => 7,
                 (7, _, _) => 8,
                 (6, 2, 4) => 9,
-                other => unreachable!("unexpected combination: {:?}", other),
+                _ => return None,
             };
             encodings[decoded] = digit;
         }
-        encodings
+        Some(encodings)
     }
 
-    fn decode_digit(&self, d: u8) -> u16 {
-        self.0.iter().position(|b| *b == d).unwrap() as u16
+    fn decode_digit(&self, d: u8) -> Option<u16> {
+        self.0.iter().position(|b| *b == d).map(|d| d as u16)
     }
 
-    fn decode_display(&self, d: [u8; 4]) -> u16 {
+    fn decode_display(&self, d: [u8; 4]) -> Option<u16> {
         d.into_iter()
-            .map(|d| self.decode_digit(d))
-            .fold(0, |acc, d| 10 * acc + d)
+            .try_fold(0, |acc, d| Some(10 * acc + self.decode_digit(d)?))
     }
 }
 
-pub fn part_2(input: &str) -> usize {
-    let mut input = input.as_bytes();
-    std::iter::from_fn(|| Some(&mut input).filter(|i| !i.is_empty()).map(parse_line))
-        .map(|(digits, display)| usize::from(Decoder::from_digits(digits).decode_display(display)))
+pub fn part_2(input: &str) -> Result<usize, Day8Error> {
+    let input = normalize::line_endings(input);
+    entries(input.as_bytes())
+        .map(|entry| {
+            let (start, (digits, display)) = entry?;
+            Decoder::from_digits(digits)
+                .and_then(|decoder| decoder.decode_display(display))
+                .map(usize::from)
+                .ok_or_else(|| Day8Error::UnknownDigit(Position::of(input.as_bytes(), start)))
+        })
         .sum()
 }
 
@@ -123,46 +190,53 @@ fn decode_digit(
     input: &mut impl Iterator<Item = u8>,
     frequency_table: [u8; 256],
     radix: usize,
-) -> usize {
+) -> Option<usize> {
     let sum = input
         // End when hitting ' ' or '\n'
         .take_while(|b| *b > (b'a' - 1))
         .map(|b| frequency_table[usize::from(b)])
         .sum();
-    radix
-        * match sum {
-            17 => 1,
-            34 => 2,
-            39 => 3,
-            30 => 4,
-            37 => 5,
-            41 => 6,
-            25 => 7,
-            49 => 8,
-            45 => 9,
-            _ => {
-                debug_assert_eq!(sum, 42);
-                0
-            }
-        }
+    let digit = match sum {
+        42 => 0,
+        17 => 1,
+        34 => 2,
+        39 => 3,
+        30 => 4,
+        37 => 5,
+        41 => 6,
+        25 => 7,
+        49 => 8,
+        45 => 9,
+        _ => return None,
+    };
+    Some(radix * digit)
 }
 
-fn decode_number(input: &mut impl Iterator<Item = u8>, frequency_table: [u8; 256]) -> usize {
+fn decode_number(
+    input: &mut impl Iterator<Item = u8>,
+    frequency_table: [u8; 256],
+) -> Option<usize> {
     (0..4)
         .rev()
         .map(|radix| decode_digit(input, frequency_table, 10_usize.pow(radix)))
         .sum()
 }
 
-pub fn part_2_frequency_table(input: &str) -> usize {
-    let mut input = input.bytes();
+pub fn part_2_frequency_table(input: &str) -> Result<usize, Day8Error> {
+    let input = normalize::line_endings(input);
+    let bytes = input.as_bytes();
+    let mut input = bytes.iter().copied();
     std::iter::from_fn(|| {
         if input.len() == 0 {
             None
         } else {
+            let start = bytes.len() - input.len();
             let frequency_table = compute_frequency_table(&mut input);
             input.next();
-            Some(decode_number(&mut input, frequency_table))
+            Some(
+                decode_number(&mut input, frequency_table)
+                    .ok_or_else(|| Day8Error::UnknownDigit(Position::of(bytes, start))),
+            )
         }
     })
     .sum()
@@ -190,23 +264,43 @@ egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg |
 gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc |
 fgae cfgab fg bagce";
-    assert_eq!(part_1(input), 26);
-}
-
-#[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day8.txt");
-    assert_eq!(part_1(input), 245);
-}
-
-#[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day8.txt");
-    assert_eq!(part_2(input), 983026);
+    assert_eq!(part_1(input), Ok(26));
 }
 
 #[test]
-fn test_part_2_frequency_table_input() {
-    let input = include_str!("../input/2021/day8.txt");
-    assert_eq!(part_2_frequency_table(input), 983026);
+fn test_parse_error() {
+    let entry =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+    assert_eq!(part_2(entry), Ok(5353));
+    assert_eq!(part_2_frequency_table(entry), Ok(5353));
+    assert_eq!(
+        parse(&format!("{}\n{}", entry, entry.replace("fbcad", "fbxad"))),
+        Err(Day8Error::ExpectedPattern(Position {
+            line: 2,
+            column: 23
+        }))
+    );
+    assert_eq!(
+        parse(&entry.replace(" | ", " / ")),
+        Err(Day8Error::ExpectedDelimiter(Position {
+            line: 1,
+            column: 60
+        }))
+    );
+    assert_eq!(
+        parse(&entry.replace(" cdbaf", "")),
+        Err(Day8Error::ExpectedPattern(Position {
+            line: 1,
+            column: 79
+        }))
+    );
+    let unknown = format!("{}\n{}", entry, entry.replace("| cdfeb", "| cdfeg"));
+    assert_eq!(
+        part_2(&unknown),
+        Err(Day8Error::UnknownDigit(Position { line: 2, column: 1 }))
+    );
+    assert_eq!(
+        part_2_frequency_table(&unknown),
+        Err(Day8Error::UnknownDigit(Position { line: 2, column: 1 }))
+    );
 }
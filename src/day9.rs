@@ -1,3 +1,13 @@
+//! Day 9: Smoke Basin. Finds the low points and the basins of a height map.
+//!
+//! ```
+//! use advent_of_code_2021::day9;
+//!
+//! let input = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678";
+//! assert_eq!(day9::part_1(input), Ok(15));
+//! assert_eq!(day9::part_2(input), Ok(1134));
+//! ```
+
 use std::{
     cmp::Ordering,
     fmt, iter,
@@ -5,11 +15,52 @@ use std::{
 };
 
 use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{
+    grid::{DigitMapError, Dynamic, Fixed, Grid, Size},
+    normalize,
+    position::Position,
+};
 
-pub fn part_1_generic<const LINE_SIZE: usize>(input: &str) -> usize {
-    debug_assert_eq!(input.find('\n'), Some(LINE_SIZE - 1));
-    iter::once(b'\n')
-        .chain(input.bytes())
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum Day9Error {
+    #[error("{0}: expected a height")]
+    ExpectedDigit(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: expected the end of the height map")]
+    ExpectedEnd(Position),
+    #[error("the height map has {0} basins instead of at least 3")]
+    TooFewBasins(usize),
+}
+
+/// The size of the height map in the puzzle input.
+type InputSize = Fixed<100, 100>;
+
+impl From<DigitMapError> for Day9Error {
+    fn from(error: DigitMapError) -> Self {
+        match error {
+            DigitMapError::ExpectedDigit(position) => Day9Error::ExpectedDigit(position),
+            DigitMapError::ExpectedNewline(position) => Day9Error::ExpectedNewline(position),
+            DigitMapError::ExpectedEnd(position) => Day9Error::ExpectedEnd(position),
+        }
+    }
+}
+
+/// Check that the input is a height map with lines of equal length.
+pub fn parse(input: &str) -> Result<&[u8], Day9Error> {
+    let grid = Grid::parse_digits(input, Dynamic::of(input.as_bytes()))?;
+    Ok(grid.as_bytes())
+}
+
+/// Solve part 1 for a height map of the given size. The input should have
+/// `\n` line endings.
+pub fn part_1_generic<S: Size>(input: &str, size: S) -> Result<usize, Day9Error> {
+    let input = Grid::parse_digits(input, size)?.as_bytes();
+    let line_size = size.width() + 1;
+    Ok(iter::once(b'\n')
+        .chain(input.iter().copied())
         .chain(iter::once(b'\n'))
         .map(|b| b ^ b'0')
         .tuple_windows()
@@ -20,17 +71,21 @@ pub fn part_1_generic<const LINE_SIZE: usize>(input: &str) -> usize {
         .filter(|(index, (cmp1, cmp2, value))| {
             *cmp1 == Ordering::Greater
                 && *cmp2 == Ordering::Less
-                && (*index < LINE_SIZE || *value < b'0' ^ input.as_bytes()[*index - LINE_SIZE])
-                && (*index >= input.len() - LINE_SIZE
-                    || *value < b'0' ^ input.as_bytes()[*index + LINE_SIZE])
+                && (*index < line_size || *value < b'0' ^ input[*index - line_size])
+                && (*index + line_size >= input.len() || *value < b'0' ^ input[*index + line_size])
         })
         .map(|(_index, (_cmp1, _cmp2, value))| usize::from(value + 1))
-        .sum()
+        .sum())
 }
 
-pub fn part_1(input: &str) -> usize {
-    const LINE_SIZE: usize = 101;
-    part_1_generic::<LINE_SIZE>(input)
+pub fn part_1(input: &str) -> Result<usize, Day9Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        part_1_generic(&input, InputSize::default())
+    } else {
+        part_1_generic(&input, size)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -79,19 +134,31 @@ impl<T> IndexMut<MaybeBasin> for Vec<T> {
     }
 }
 
-fn part_2_generic<const LINE_SIZE: usize>(input: &str) -> usize {
-    debug_assert_eq!(input.find('\n'), Some(LINE_SIZE - 1));
+/// Solve part 2 for a height map of the given size. The input should have
+/// `\n` line endings.
+pub fn part_2_generic<S: Size>(input: &str, size: S) -> Result<usize, Day9Error> {
+    let input = Grid::parse_digits(input, size)?.as_bytes();
+    let line_size = size.width() + 1;
 
-    // Really need to comput size of areas != 9.
-
-    // Stores the size of a basin by their index
+    // Stores the size of a basin by their index. Basins that turn out to be
+    // connected are merged into the one with the index in `merged_into`, like
+    // in a union-find, and their size is moved along.
     let mut basin_size = Vec::new();
+    let mut merged_into = Vec::new();
+    let find = |merged_into: &mut Vec<usize>, mut basin: MaybeBasin| {
+        while merged_into[basin] != basin.0 {
+            let next = merged_into[merged_into[basin]];
+            merged_into[basin] = next;
+            basin = MaybeBasin(next);
+        }
+        basin
+    };
     // The next basin index to use
     let mut next_basin = MaybeBasin(0);
     // Basins already associated with a tile
-    let mut associated_basins = [MaybeBasin::no_basin(); LINE_SIZE];
-    for (index, b) in input.bytes().enumerate() {
-        let x = index % LINE_SIZE;
+    let mut associated_basins = vec![MaybeBasin::no_basin(); line_size];
+    for (index, &b) in input.iter().enumerate() {
+        let x = index % line_size;
         match b {
             b'9' | b'\n' => {
                 // No basin
@@ -99,48 +166,56 @@ fn part_2_generic<const LINE_SIZE: usize>(input: &str) -> usize {
             }
             _ => {
                 let basin_above = associated_basins[x];
+                let basin_above = if basin_above.is_a_basin() {
+                    find(&mut merged_into, basin_above)
+                } else {
+                    basin_above
+                };
                 let basin_to_the_left = if x >= 1 {
                     associated_basins[x - 1]
                 } else {
                     MaybeBasin::no_basin()
                 };
-                if basin_to_the_left.is_a_basin() {
-                    if basin_above.is_no_basin() || basin_above == basin_to_the_left {
-                        associated_basins[x] = basin_to_the_left;
-                        basin_size[basin_to_the_left] += 1;
+                associated_basins[x] = if basin_to_the_left.is_a_basin() {
+                    if basin_above.is_a_basin() && basin_above != basin_to_the_left {
+                        basin_size[basin_above] +=
+                            std::mem::take(&mut basin_size[basin_to_the_left]);
+                        merged_into[basin_to_the_left] = basin_above.0;
+                        basin_above
                     } else {
-                        for previous in (0..x).rev() {
-                            if associated_basins[previous] != basin_to_the_left {
-                                break;
-                            }
-                            associated_basins[previous] = basin_above;
-                        }
-                        basin_size[basin_above] += basin_size[basin_to_the_left] + 1;
+                        basin_to_the_left
                     }
                 } else if basin_above.is_no_basin() {
-                    assert_eq!(basin_size.len(), next_basin.0); // For some reason actually using this is not faster
-                    basin_size.push(1);
-                    associated_basins[x] = next_basin;
+                    basin_size.push(0);
+                    merged_into.push(next_basin.0);
+                    let basin = next_basin;
                     next_basin.increment();
+                    basin
                 } else {
-                    associated_basins[x] = basin_above;
-                    basin_size[basin_above] += 1;
+                    basin_above
                 };
+                basin_size[associated_basins[x]] += 1;
             }
         }
     }
-    let size = basin_size.len();
-    basin_size
-        .select_nth_unstable(size.saturating_sub(4))
-        .2
-        .iter()
-        .copied()
-        .product()
+    // Merged basins are left with a size of 0
+    basin_size.sort_unstable_by(|a, b| b.cmp(a));
+    match basin_size[..] {
+        [a, b, c, ..] if c > 0 => Ok(a * b * c),
+        _ => Err(Day9Error::TooFewBasins(
+            basin_size.iter().filter(|size| **size > 0).count(),
+        )),
+    }
 }
 
-pub fn part_2(input: &str) -> usize {
-    const LINE_SIZE: usize = 101;
-    part_2_generic::<LINE_SIZE>(input)
+pub fn part_2(input: &str) -> Result<usize, Day9Error> {
+    let input = normalize::line_endings(input);
+    let size = Dynamic::of(input.as_bytes());
+    if size.is::<InputSize>() {
+        part_2_generic(&input, InputSize::default())
+    } else {
+        part_2_generic(&input, size)
+    }
 }
 
 #[test]
@@ -150,13 +225,43 @@ fn test_part_1_example() {
 9856789892
 8767896789
 9899965678";
-    assert_eq!(part_1_generic::<11>(input), 15);
+    assert_eq!(part_1_generic(input, Fixed::<10, 5>), Ok(15));
+    assert_eq!(part_1(input), Ok(15));
 }
 
 #[test]
-fn test_part_1_input() {
-    let input = include_str!("../input/2021/day9.txt");
-    assert_eq!(part_1(input), 530);
+fn test_parse_error() {
+    assert_eq!(parse("219\n398"), Ok(b"219\n398".as_ref()));
+    assert_eq!(
+        parse("219\n3x8"),
+        Err(Day9Error::ExpectedDigit(Position { line: 2, column: 2 }))
+    );
+    assert_eq!(
+        parse("219\n39\n"),
+        Err(Day9Error::ExpectedDigit(Position { line: 2, column: 3 }))
+    );
+    assert_eq!(
+        parse("219\n3988"),
+        Err(Day9Error::ExpectedNewline(Position { line: 2, column: 4 }))
+    );
+    assert_eq!(
+        part_1_generic("2199\n398", Fixed::<3, 2>),
+        Err(Day9Error::ExpectedNewline(Position { line: 1, column: 4 }))
+    );
+    assert_eq!(
+        part_2_generic("219\n39", Fixed::<3, 2>),
+        Err(Day9Error::ExpectedDigit(Position { line: 2, column: 3 }))
+    );
+    assert_eq!(
+        part_2_generic("219\n398\n123", Fixed::<3, 2>),
+        Err(Day9Error::ExpectedEnd(Position { line: 3, column: 1 }))
+    );
+}
+
+#[test]
+fn test_part_1_single_row() {
+    assert_eq!(part_1("5"), Ok(6));
+    assert_eq!(part_1("2199"), Ok(2));
 }
 
 #[test]
@@ -166,11 +271,22 @@ fn test_part_2_example() {
 9856789892
 8767896789
 9899965678";
-    assert_eq!(part_2_generic::<11>(input), 1134);
+    assert_eq!(part_2_generic(input, Fixed::<10, 5>), Ok(1134));
+    assert_eq!(part_2(input), Ok(1134));
+}
+
+#[test]
+fn test_part_2_few_basins() {
+    assert_eq!(part_2("1191119111"), Ok(18));
+    assert_eq!(part_2("9"), Err(Day9Error::TooFewBasins(0)));
+    assert_eq!(part_2("99\n99"), Err(Day9Error::TooFewBasins(0)));
+    assert_eq!(part_2("1191\n9911"), Err(Day9Error::TooFewBasins(2)));
 }
 
 #[test]
-fn test_part_2_input() {
-    let input = include_str!("../input/2021/day9.txt");
-    assert_eq!(part_2(input), 1019494);
+fn test_part_2_merged_basins() {
+    // The two top left basins only meet on the third line
+    assert_eq!(part_2("19191\n19191\n11191\n99999\n19191"), Ok(21));
+    // A basin shaped like a U
+    assert_eq!(part_2("9191\n9111\n9999\n1919\n9999\n9191"), Ok(5));
 }
@@ -0,0 +1,142 @@
+//! Differential tests, which run every variant of a part on random inputs and
+//! check that they all give the same answer.
+
+use itertools::Itertools;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    generate,
+    solver::{Answer, Solver, SOLVERS},
+};
+
+/// The sizes of the inputs to try, from small to large so that the first
+/// input on which the variants disagree is likely to be small.
+const SIZES: [usize; 4] = [1, 4, 50, 500];
+/// The number of random inputs to try for every size.
+const INPUTS_PER_SIZE: u64 = 25;
+
+type Generator = fn(&mut ChaCha8Rng, usize) -> String;
+
+/// The generator for inputs for the day, and the separator between the items
+/// in the input that can be left out without making it invalid.
+fn generator(day: u8) -> Option<(Generator, &'static str)> {
+    match day {
+        1 => Some((generate::day1, "\n")),
+        3 => Some((generate::day3, "\n")),
+        7 => Some((generate::day7, ",")),
+        8 => Some((generate::day8, "\n")),
+        _ => None,
+    }
+}
+
+/// The answers of the solvers, or an error if any of them rejects the input.
+fn answers(solvers: &[&dyn Solver], input: &str) -> anyhow::Result<Vec<Answer>> {
+    solvers.iter().map(|solver| solver.solve(input)).collect()
+}
+
+fn disagree(solvers: &[&dyn Solver], input: &str) -> bool {
+    answers(solvers, input).is_ok_and(|answers| !answers.iter().all_equal())
+}
+
+/// Leave out as many items of the input as possible while the solvers keep
+/// disagreeing, first in large chunks and then one by one.
+fn shrink(solvers: &[&dyn Solver], input: &str, separator: &str) -> String {
+    let mut items: Vec<&str> = input.split(separator).collect();
+    let mut chunk_size = items.len() / 2;
+    while chunk_size > 0 {
+        let mut start = 0;
+        while start < items.len() && items.len() > 1 {
+            let end = (start + chunk_size).min(items.len());
+            let candidate = items[..start].iter().chain(&items[end..]).join(separator);
+            if end - start < items.len() && disagree(solvers, &candidate) {
+                items.drain(start..end);
+            } else {
+                start = end;
+            }
+        }
+        chunk_size /= 2;
+    }
+    items.join(separator)
+}
+
+/// Check that all variants of the part agree on random inputs, and panic with
+/// the smallest input found on which they do not.
+fn check_variants(solvers: &[&dyn Solver], generate: Generator, separator: &str) {
+    for (size_index, &size) in SIZES.iter().enumerate() {
+        for i in 0..INPUTS_PER_SIZE {
+            let seed = size_index as u64 * INPUTS_PER_SIZE + i;
+            let input = generate(&mut ChaCha8Rng::seed_from_u64(seed), size);
+            let first_answers = answers(solvers, &input).unwrap_or_else(|e| {
+                panic!(
+                    "generated input {:?} (seed {}) is rejected: {}",
+                    input, seed, e
+                )
+            });
+            if first_answers.iter().all_equal() {
+                continue;
+            }
+            let minimal = shrink(solvers, &input, separator);
+            let minimal_answers = solvers
+                .iter()
+                .zip(answers(solvers, &minimal).unwrap())
+                .map(|(solver, answer)| format!("{}: {}", solver.variant(), answer))
+                .join(", ");
+            panic!(
+                "the variants of day {} part {} disagree on {:?} (shrunk from seed {}): {}",
+                solvers[0].day(),
+                solvers[0].part(),
+                minimal,
+                seed,
+                minimal_answers
+            );
+        }
+    }
+}
+
+#[test]
+fn test_variants_agree() {
+    let parts = SOLVERS
+        .iter()
+        .copied()
+        .into_group_map_by(|solver| (solver.day(), solver.part()));
+    for ((day, part), solvers) in parts.into_iter().sorted_by_key(|(key, _)| *key) {
+        if solvers.len() < 2 {
+            continue;
+        }
+        let (generate, separator) = generator(day)
+            .unwrap_or_else(|| panic!("day {} part {} has variants but no generator", day, part));
+        check_variants(&solvers, generate, separator);
+    }
+}
+
+/// A solver that counts the lines containing a 7, or that always returns 0.
+#[cfg(test)]
+struct CountSevens(bool);
+
+#[cfg(test)]
+impl Solver for CountSevens {
+    fn day(&self) -> u8 {
+        0
+    }
+
+    fn part(&self) -> u8 {
+        1
+    }
+
+    fn variant(&self) -> &'static str {
+        "test"
+    }
+
+    fn solve(&self, input: &str) -> anyhow::Result<Answer> {
+        let count = input.lines().filter(|l| self.0 && l.contains('7')).count();
+        Ok(Answer::Number(count as i128))
+    }
+}
+
+#[test]
+fn test_shrink() {
+    let solvers: [&dyn Solver; 2] = [&CountSevens(true), &CountSevens(false)];
+    assert_eq!(shrink(&solvers, "1\n7\n3\n77\n5", "\n"), "77");
+    assert_eq!(shrink(&solvers, "7", "\n"), "7");
+}
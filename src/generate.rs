@@ -0,0 +1,805 @@
+//! Generators for random puzzle inputs. Every generator takes the random
+//! number generator to use and a size, which is roughly the number of lines or
+//! items in the input. The inputs keep to the same rules as the real puzzle
+//! inputs, so that every day can solve them.
+
+use std::collections::{BTreeSet, HashSet};
+
+use itertools::Itertools;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::day19::Rotation;
+
+/// Generate an input for the day from the seed, or `None` if there is no
+/// generator for that day.
+pub fn generate(day: u8, seed: u64, size: usize) -> Option<String> {
+    let generator: fn(&mut ChaCha8Rng, usize) -> String = match day {
+        1 => day1,
+        2 => day2,
+        3 => day3,
+        4 => day4,
+        5 => day5,
+        6 => day6,
+        7 => day7,
+        8 => day8,
+        9 => day9,
+        10 => day10,
+        11 => day11,
+        12 => day12,
+        13 => day13,
+        14 => day14,
+        15 => day15,
+        16 => day16,
+        17 => day17,
+        18 => day18,
+        19 => day19,
+        20 => day20,
+        21 => day21,
+        22 => day22,
+        23 => day23,
+        24 => day24,
+        25 => day25,
+        _ => return None,
+    };
+    Some(generator(&mut ChaCha8Rng::seed_from_u64(seed), size))
+}
+
+/// `size` depths that slowly go down.
+pub fn day1(rng: &mut impl Rng, size: usize) -> String {
+    let mut depth: u32 = rng.gen_range(100, 200);
+    (0..size)
+        .map(|_| {
+            depth = depth
+                .saturating_add(rng.gen_range(0, 40))
+                .saturating_sub(10);
+            depth
+        })
+        .join("\n")
+}
+
+/// `size` commands, which never take the submarine above the surface.
+pub fn day2(rng: &mut impl Rng, size: usize) -> String {
+    let mut depth = 0;
+    (0..size)
+        .map(|_| {
+            let magnitude = rng.gen_range(1, 10);
+            match rng.gen_range(0, 3) {
+                0 => format!("forward {}", magnitude),
+                1 if magnitude <= depth => {
+                    depth -= magnitude;
+                    format!("up {}", magnitude)
+                }
+                _ => {
+                    depth += magnitude;
+                    format!("down {}", magnitude)
+                }
+            }
+        })
+        .join("\n")
+}
+
+/// A diagnostic report of `size` numbers of 12 bits.
+pub fn day3(rng: &mut impl Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            (0..12)
+                .map(|_| if rng.gen_bool(0.5) { '1' } else { '0' })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// The number of numbers drawn before the card wins.
+fn winning_turn(card: &[u8], turns: &[usize; 100]) -> usize {
+    let turn = |i: usize| turns[usize::from(card[i])];
+    let rows = (0..5).map(|row| (0..5).map(|col| turn(row * 5 + col)).max().unwrap());
+    let cols = (0..5).map(|col| (0..5).map(|row| turn(row * 5 + col)).max().unwrap());
+    rows.chain(cols).min().unwrap()
+}
+
+/// The numbers 0 to 99 in a random order and `size` bingo cards. Since every
+/// number is drawn, every card wins eventually. Cards are drawn again until
+/// there is a single card that wins last, like in the puzzle input.
+pub fn day4(rng: &mut impl Rng, size: usize) -> String {
+    let mut numbers: Vec<u8> = (0..100).collect();
+    numbers.shuffle(rng);
+    let mut turns = [0; 100];
+    for (turn, number) in numbers.iter().enumerate() {
+        turns[usize::from(*number)] = turn;
+    }
+    let cards = loop {
+        let cards = (0..size)
+            .map(|_| numbers.choose_multiple(rng, 25).copied().collect_vec())
+            .collect_vec();
+        let winning_turns = cards
+            .iter()
+            .map(|card| winning_turn(card, &turns))
+            .sorted()
+            .collect_vec();
+        if size < 2 || winning_turns[size - 2] < winning_turns[size - 1] {
+            break cards;
+        }
+    };
+    let mut input = numbers.iter().join(",");
+    for card in cards {
+        input.push('\n');
+        for row in card.chunks(5) {
+            input.push('\n');
+            input.push_str(&row.iter().map(|n| format!("{:>2}", n)).join(" "));
+        }
+    }
+    input
+}
+
+/// `size` horizontal, vertical and diagonal lines of vents, with coordinates
+/// below 1000.
+pub fn day5(rng: &mut impl Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let (x1, y1) = (rng.gen_range(0, 1000), rng.gen_range(0, 1000));
+            let len = rng.gen_range(0, 500);
+            let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)]
+                .choose(rng)
+                .copied()
+                .unwrap();
+            let sign = *[-1, 1].choose(rng).unwrap();
+            // Shorten the line where it would leave the grid
+            let fits = |len: i32| {
+                let (x2, y2) = (x1 + sign * dx * len, y1 + sign * dy * len);
+                (0..1000).contains(&x2) && (0..1000).contains(&y2)
+            };
+            let len = (0..=len).rev().find(|len| fits(*len)).unwrap();
+            let (x2, y2) = (x1 + sign * dx * len, y1 + sign * dy * len);
+            format!("{},{} -> {},{}", x1, y1, x2, y2)
+        })
+        .join("\n")
+}
+
+/// The timers of `size` lanternfish.
+pub fn day6(rng: &mut impl Rng, size: usize) -> String {
+    (0..size).map(|_| rng.gen_range(1, 6)).join(",")
+}
+
+/// The positions of `size` crabs, all less than 2000.
+pub fn day7(rng: &mut impl Rng, size: usize) -> String {
+    // Vary the spread, so that the crabs are not always centered around 1000
+    let max = rng.gen_range(1, 2000);
+    (0..size).map(|_| rng.gen_range(0, max)).join(",")
+}
+
+/// The segments of the digits 0 to 9 on a display that is wired correctly.
+const DIGIT_SEGMENTS: [&[u8]; 10] = [
+    b"abcefg", b"cf", b"acdeg", b"acdfg", b"bcdf", b"abdfg", b"abdefg", b"acf", b"abcdefg",
+    b"abcdfg",
+];
+
+/// The signal pattern for `digit` on a display with the given wiring, with the
+/// segments in a random order.
+fn scrambled_pattern(rng: &mut impl Rng, wiring: &[u8; 7], digit: usize) -> String {
+    let mut pattern: Vec<u8> = DIGIT_SEGMENTS[digit]
+        .iter()
+        .map(|segment| wiring[usize::from(segment - b'a')])
+        .collect();
+    pattern.shuffle(rng);
+    String::from_utf8(pattern).unwrap()
+}
+
+/// `size` notes of a display with randomly mixed up wires.
+pub fn day8(rng: &mut impl Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let mut wiring = *b"abcdefg";
+            wiring.shuffle(rng);
+            let mut digits: Vec<usize> = (0..10).collect();
+            digits.shuffle(rng);
+            let patterns = digits
+                .into_iter()
+                .map(|digit| scrambled_pattern(rng, &wiring, digit))
+                .join(" ");
+            let output = (0..4)
+                .map(|_| {
+                    let digit = rng.gen_range(0, 10);
+                    scrambled_pattern(rng, &wiring, digit)
+                })
+                .join(" ");
+            format!("{} | {}", patterns, output)
+        })
+        .join("\n")
+}
+
+/// A grid of `width` by `height` digits, where `digit` picks each of them.
+fn digit_grid<R: Rng>(
+    rng: &mut R,
+    width: usize,
+    height: usize,
+    mut digit: impl FnMut(&mut R) -> u8,
+) -> String {
+    (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| char::from(b'0' + digit(rng)))
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// A height map of `size` by `size`. Around a fifth of the locations have
+/// height 9, which splits the map into basins. The second and fourth column
+/// are walls of height 9, with the first location of the columns next to them
+/// lower, so that there are at least three basins.
+pub fn day9(rng: &mut impl Rng, size: usize) -> String {
+    let size = size.max(5);
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| match (x, y) {
+                    (1 | 3, _) => 9,
+                    (0 | 2 | 4, 0) => rng.gen_range(0, 9),
+                    _ if rng.gen_bool(0.2) => 9,
+                    _ => rng.gen_range(0, 9),
+                })
+                .map(|digit| char::from(b'0' + digit))
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// `size` lines of chunks, which are either corrupted or incomplete. The
+/// number of incomplete lines is odd, so that there is a middle score.
+pub fn day10(rng: &mut impl Rng, size: usize) -> String {
+    const OPENERS: &[u8] = b"([{<";
+    const CLOSERS: &[u8] = b")]}>";
+    let size = size.max(1);
+    let incomplete = {
+        let incomplete = rng.gen_range(0, size) | 1;
+        incomplete.min(size - 1 + size % 2)
+    };
+    let mut lines = (0..size)
+        .map(|i| {
+            let corrupted = i >= incomplete;
+            let len = rng.gen_range(20, 110);
+            let mut line = Vec::with_capacity(len);
+            let mut stack = Vec::new();
+            for _ in 0..len {
+                if stack.is_empty() || (stack.len() < 60 && rng.gen_bool(0.55)) {
+                    let opener = rng.gen_range(0, 4);
+                    stack.push(opener);
+                    line.push(OPENERS[opener]);
+                } else {
+                    line.push(CLOSERS[stack.pop().unwrap()]);
+                }
+            }
+            if stack.is_empty() {
+                let opener = rng.gen_range(0, 4);
+                stack.push(opener);
+                line.push(OPENERS[opener]);
+            }
+            if corrupted {
+                // Close the last open chunk with the wrong bracket
+                let wrong = (stack.last().unwrap() + rng.gen_range(1, 4)) % 4;
+                line.push(CLOSERS[wrong]);
+            }
+            String::from_utf8(line).unwrap()
+        })
+        .collect_vec();
+    lines.shuffle(rng);
+    lines.join("\n")
+}
+
+/// Whether all octopuses flash at once within a thousand steps, with the
+/// energy levels stored row by row.
+fn octopuses_synchronise(mut levels: Vec<u8>, size: usize) -> bool {
+    (0..1000).any(|_| {
+        let mut flashing = Vec::new();
+        for (i, level) in levels.iter_mut().enumerate() {
+            *level += 1;
+            if *level == 10 {
+                flashing.push(i);
+            }
+        }
+        let mut flashes = 0;
+        while let Some(i) = flashing.pop() {
+            flashes += 1;
+            let (x, y) = (i % size, i / size);
+            for ny in y.saturating_sub(1)..(y + 2).min(size) {
+                for nx in x.saturating_sub(1)..(x + 2).min(size) {
+                    let level = &mut levels[ny * size + nx];
+                    *level += 1;
+                    if *level == 10 {
+                        flashing.push(ny * size + nx);
+                    }
+                }
+            }
+        }
+        for level in levels.iter_mut().filter(|level| **level > 9) {
+            *level = 0;
+        }
+        flashes == size * size
+    })
+}
+
+/// A grid of `size` by `size` octopuses that synchronise eventually. Many
+/// random grids never do, so grids are drawn until one does. Energy levels
+/// that are closer together synchronise sooner, so the spread of the levels
+/// varies as well.
+pub fn day11(rng: &mut impl Rng, size: usize) -> String {
+    loop {
+        let spread = rng.gen_range(2, 11);
+        let lowest = rng.gen_range(0, 11 - spread);
+        let levels = (0..size * size)
+            .map(|_| lowest + rng.gen_range(0, spread))
+            .collect_vec();
+        if octopuses_synchronise(levels.clone(), size) {
+            return levels
+                .chunks(size.max(1))
+                .map(|row| {
+                    row.iter()
+                        .map(|level| char::from(b'0' + level))
+                        .collect::<String>()
+                })
+                .join("\n");
+        }
+    }
+}
+
+/// A cave system with `size` caves apart from the start and end, up to eight.
+/// Big caves are never connected to each other, so that the number of paths
+/// is finite.
+pub fn day12(rng: &mut impl Rng, size: usize) -> String {
+    let mut caves = vec!["start".to_string(), "end".to_string()];
+    let mut names = HashSet::new();
+    while caves.len() < size.min(8) + 2 {
+        let big = rng.gen_bool(0.3);
+        let (first, last) = if big { (b'A', b'Z') } else { (b'a', b'z') };
+        let name: String = (0..2)
+            .map(|_| char::from(rng.gen_range(first, last + 1)))
+            .collect();
+        if names.insert(name.to_ascii_lowercase()) {
+            caves.push(name);
+        }
+    }
+    let is_big = |cave: &str| cave.bytes().all(|b| b.is_ascii_uppercase());
+    let mut connections = BTreeSet::new();
+    // A path from the start to the end through every cave, then some shortcuts
+    let mut path = (2..caves.len()).collect_vec();
+    path.shuffle(rng);
+    path.insert(0, 0);
+    path.push(1);
+    for (a, b) in path.iter().tuple_windows() {
+        if is_big(&caves[*a]) && is_big(&caves[*b]) {
+            // Go through a small cave instead
+            continue;
+        }
+        connections.insert((*a.min(b), *a.max(b)));
+    }
+    for _ in 0..caves.len() {
+        let (a, b) = (rng.gen_range(0, caves.len()), rng.gen_range(0, caves.len()));
+        if a != b && !(is_big(&caves[a]) && is_big(&caves[b])) {
+            connections.insert((a.min(b), a.max(b)));
+        }
+    }
+    // Connect caves that were skipped because of two big neighbours
+    for (i, cave) in caves.iter().enumerate().skip(2) {
+        if !connections.iter().any(|(a, b)| *a == i || *b == i) {
+            let neighbour = if is_big(cave) { 0 } else { 1 };
+            connections.insert((neighbour.min(i), neighbour.max(i)));
+        }
+    }
+    let mut connections = connections
+        .into_iter()
+        .map(|(a, b)| {
+            if rng.gen_bool(0.5) {
+                format!("{}-{}", caves[a], caves[b])
+            } else {
+                format!("{}-{}", caves[b], caves[a])
+            }
+        })
+        .collect_vec();
+    connections.sort_unstable();
+    connections.shuffle(rng);
+    connections.join("\n")
+}
+
+/// `size` dots, which spell eight letters of five by six after folding along
+/// the twelve fold lines.
+pub fn day13(rng: &mut impl Rng, size: usize) -> String {
+    // Folding along `along` halves a width of `2 * along + 1`
+    let unfold = |sizes: &mut Vec<u16>| sizes.push(2 * sizes.last().unwrap() + 1);
+    let (mut x_folds, mut y_folds) = (vec![40], vec![6]);
+    for _ in 0..4 {
+        unfold(&mut x_folds);
+    }
+    for _ in 0..6 {
+        unfold(&mut y_folds);
+    }
+    let folds = x_folds
+        .iter()
+        .rev()
+        .map(|along| (b'x', *along))
+        .interleave(y_folds.iter().rev().map(|along| (b'y', *along)))
+        .collect_vec();
+    let dots = (0..size).map(|_| {
+        // Pick a point on the folded paper, and unfold it again
+        let mut dot = [rng.gen_range(0, 40), rng.gen_range(0, 6)];
+        for (axis, along) in folds.iter().rev() {
+            let coordinate = &mut dot[usize::from(axis - b'x')];
+            if rng.gen_bool(0.5) {
+                *coordinate = 2 * along - *coordinate;
+            }
+        }
+        format!("{},{}", dot[0], dot[1])
+    });
+    let folds = folds
+        .iter()
+        .map(|(axis, along)| format!("fold along {}={}", char::from(*axis), along));
+    format!(
+        "{}\n\n{}",
+        dots.collect_vec().join("\n"),
+        folds.collect_vec().join("\n")
+    )
+}
+
+/// A polymer template of `size` elements, with a rule for every pair of the
+/// ten elements used.
+pub fn day14(rng: &mut impl Rng, size: usize) -> String {
+    let mut elements: Vec<char> = ('A'..='Z').collect();
+    elements.shuffle(rng);
+    elements.truncate(10);
+    let template: String = (0..size.max(2))
+        .map(|_| *elements.choose(rng).unwrap())
+        .collect();
+    let rules = elements
+        .iter()
+        .cartesian_product(elements.iter())
+        .map(|(a, b)| format!("{}{} -> {}", a, b, elements.choose(rng).unwrap()))
+        .collect_vec();
+    format!("{}\n\n{}", template, rules.join("\n"))
+}
+
+/// A map of `size` by `size` risk levels from 1 to 9.
+pub fn day15(rng: &mut impl Rng, size: usize) -> String {
+    digit_grid(rng, size, size, |rng| rng.gen_range(1, 10))
+}
+
+/// Append the `len` least significant bits of `value` to `bits`.
+fn push_bits(bits: &mut Vec<bool>, value: usize, len: usize) {
+    bits.extend((0..len).rev().map(|i| value & (1 << i) != 0));
+}
+
+/// Encode a random packet with about `size` packets in total, and return its
+/// value. Values are kept small enough to not overflow.
+fn encode_packet(rng: &mut impl Rng, bits: &mut Vec<bool>, size: usize) -> u64 {
+    let version = rng.gen_range(0, 8);
+    if size <= 1 {
+        let bits_used = rng.gen_range(1, 16);
+        let value: u64 = rng.gen_range(0, 1 << bits_used);
+        push_bits(bits, version, 3);
+        push_bits(bits, 4, 3);
+        let groups = (64 - value.leading_zeros() as usize).max(1).div_ceil(4);
+        for group in (0..groups).rev() {
+            bits.push(group > 0);
+            push_bits(bits, (value >> (4 * group)) as usize & 0b1111, 4);
+        }
+        return value;
+    }
+    // Comparisons have exactly two sub-packets
+    // Comparisons need room for two sub-packets
+    let type_ids: &[usize] = if size >= 3 {
+        &[0, 1, 2, 3, 5, 6, 7]
+    } else {
+        &[0, 1, 2, 3]
+    };
+    let mut type_id = *type_ids.choose(rng).unwrap();
+    let sub_packets = if type_id >= 5 {
+        2
+    } else {
+        rng.gen_range(1, 5.min(size))
+    };
+    let mut sub_bits = Vec::new();
+    let mut values = Vec::new();
+    let mut remaining = size - 1;
+    for i in 0..sub_packets {
+        let sub_size = if i + 1 == sub_packets {
+            remaining
+        } else {
+            rng.gen_range(1, remaining - (sub_packets - i - 1) + 1)
+        };
+        remaining -= sub_size;
+        values.push(encode_packet(rng, &mut sub_bits, sub_size));
+    }
+    let value = match type_id {
+        0 => values.iter().sum(),
+        1 => match values.iter().try_fold(1u64, |acc, v| acc.checked_mul(*v)) {
+            Some(product) if product < 1 << 32 => product,
+            _ => {
+                // Sum instead of multiplying large values
+                type_id = 0;
+                values.iter().sum()
+            }
+        },
+        2 => *values.iter().min().unwrap(),
+        3 => *values.iter().max().unwrap(),
+        5 => u64::from(values[0] > values[1]),
+        6 => u64::from(values[0] < values[1]),
+        _ => u64::from(values[0] == values[1]),
+    };
+    push_bits(bits, version, 3);
+    push_bits(bits, type_id, 3);
+    if sub_bits.len() < 1 << 15 && rng.gen_bool(0.5) {
+        bits.push(false);
+        push_bits(bits, sub_bits.len(), 15);
+    } else {
+        bits.push(true);
+        push_bits(bits, sub_packets, 11);
+    }
+    bits.extend(sub_bits);
+    value
+}
+
+/// A transmission of about `size` packets, padded with zeroes.
+pub fn day16(rng: &mut impl Rng, size: usize) -> String {
+    let mut bits = Vec::new();
+    encode_packet(rng, &mut bits, size.max(1));
+    bits.chunks(4)
+        .map(|nibble| {
+            let value = (0..4).fold(0, |acc, i| {
+                acc << 1 | u32::from(nibble.get(i) == Some(&true))
+            });
+            std::char::from_digit(value, 16)
+                .unwrap()
+                .to_ascii_uppercase()
+        })
+        .collect()
+}
+
+/// A target area below and to the right of the probe, roughly `size` away.
+pub fn day17(rng: &mut impl Rng, size: usize) -> String {
+    let size = size.max(1) as i32;
+    let x_from = rng.gen_range(size, 2 * size + 1);
+    let x_to = x_from + rng.gen_range(0, size);
+    let y_from = -rng.gen_range(size, 2 * size + 1);
+    let y_to = (y_from + rng.gen_range(0, size)).min(-1);
+    format!(
+        "target area: x={}..{}, y={}..{}",
+        x_from, x_to, y_from, y_to
+    )
+}
+
+/// A reduced snailfish number, nested in `depth` pairs.
+fn snailfish_number(rng: &mut impl Rng, depth: usize) -> String {
+    if depth > 0 && (depth >= 4 || rng.gen_bool(0.3)) {
+        rng.gen_range(0, 10).to_string()
+    } else {
+        format!(
+            "[{},{}]",
+            snailfish_number(rng, depth + 1),
+            snailfish_number(rng, depth + 1)
+        )
+    }
+}
+
+/// `size` reduced snailfish numbers.
+pub fn day18(rng: &mut impl Rng, size: usize) -> String {
+    (0..size).map(|_| snailfish_number(rng, 0)).join("\n")
+}
+
+type Point = [i32; 3];
+
+/// The reports of `size` scanners. Every scanner shares at least twelve
+/// beacons with one of the scanners before it.
+pub fn day19(rng: &mut impl Rng, size: usize) -> String {
+    const RANGE: i32 = 1000;
+    let in_range = |p: Point, s: Point| (0..3).all(|i| (p[i] - s[i]).abs() <= RANGE);
+    let mut positions: Vec<Point> = vec![[0, 0, 0]];
+    let mut beacons = HashSet::new();
+    while positions.len() < size.max(1) {
+        // Place the next scanner next to an earlier one
+        let linked = *positions.choose(rng).unwrap();
+        let mut position = linked;
+        for coordinate in position.iter_mut() {
+            *coordinate += rng.gen_range(-150, 151);
+        }
+        position[rng.gen_range(0, 3)] += *[-1, 1].choose(rng).unwrap() * rng.gen_range(1000, 1200);
+        let centre: Point = [0, 1, 2].map(|i| (position[i] + linked[i]) / 2);
+        let mut shared = 0;
+        while shared < 15 {
+            let beacon = [0, 1, 2].map(|i| centre[i] + rng.gen_range(-400, 401));
+            if in_range(beacon, position) && in_range(beacon, linked) && beacons.insert(beacon) {
+                shared += 1;
+            }
+        }
+        positions.push(position);
+    }
+    for position in positions.iter() {
+        for _ in 0..10 {
+            beacons.insert([0, 1, 2].map(|i| position[i] + rng.gen_range(-RANGE, RANGE + 1)));
+        }
+    }
+    let rotations = Rotation::all().collect_vec();
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let rotation = rotations.choose(rng).unwrap().inverse();
+            let mut report = vec![format!("--- scanner {} ---", i)];
+            let mut seen = beacons
+                .iter()
+                .filter(|b| in_range(**b, *position))
+                .map(|b| rotation.apply([0, 1, 2].map(|i| b[i] - position[i])))
+                .collect_vec();
+            seen.sort_unstable();
+            seen.shuffle(rng);
+            report.extend(seen.iter().map(|[x, y, z]| format!("{},{},{}", x, y, z)));
+            report.join("\n")
+        })
+        .join("\n\n")
+}
+
+/// A random image enhancement algorithm and an image of `size` by `size`.
+/// The algorithm never lights up all of the infinite image at once.
+pub fn day20(rng: &mut impl Rng, size: usize) -> String {
+    let pixel = |rng: &mut _| if Rng::gen_bool(rng, 0.5) { '#' } else { '.' };
+    let mut algorithm: Vec<char> = (0..512).map(|_| pixel(rng)).collect();
+    if algorithm[0] == '#' {
+        algorithm[511] = '.';
+    }
+    let image = (0..size)
+        .map(|_| (0..size).map(|_| pixel(rng)).collect::<String>())
+        .join("\n");
+    format!("{}\n\n{}", algorithm.into_iter().collect::<String>(), image)
+}
+
+/// The starting positions of both players. The size is not used.
+pub fn day21(rng: &mut impl Rng, _size: usize) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+        rng.gen_range(1, 11),
+        rng.gen_range(1, 11)
+    )
+}
+
+/// `size` reboot steps. Like in the puzzle input, the first half of them is
+/// within the initialization area.
+pub fn day22(rng: &mut impl Rng, size: usize) -> String {
+    (0..size)
+        .map(|i| {
+            let (extent, max_len) = if i < size / 2 {
+                (50, 30)
+            } else {
+                (100_000, 30_000)
+            };
+            let ranges = (0..3)
+                .map(|_| {
+                    let from = rng.gen_range(-extent, extent - max_len);
+                    let to = from + rng.gen_range(0, max_len);
+                    (from, to)
+                })
+                .collect_vec();
+            format!(
+                "{} x={}..{},y={}..{},z={}..{}",
+                if i == 0 || rng.gen_bool(0.7) {
+                    "on"
+                } else {
+                    "off"
+                },
+                ranges[0].0,
+                ranges[0].1,
+                ranges[1].0,
+                ranges[1].1,
+                ranges[2].0,
+                ranges[2].1
+            )
+        })
+        .join("\n")
+}
+
+/// A burrow with the amphipods in a random order. The size is not used.
+pub fn day23(rng: &mut impl Rng, _size: usize) -> String {
+    let mut amphipods = *b"AABBCCDD";
+    amphipods.shuffle(rng);
+    let room = |i: usize| char::from(amphipods[i]);
+    format!(
+        "#############\n#...........#\n###{}#{}#{}#{}###\n  #{}#{}#{}#{}#\n  #########",
+        room(0),
+        room(1),
+        room(2),
+        room(3),
+        room(4),
+        room(5),
+        room(6),
+        room(7)
+    )
+}
+
+/// A MONAD program that reads `2 * size` digits, up to 18, and accepts some of
+/// the model numbers. Every block either pushes its digit, or pops an earlier
+/// digit and checks it against its own.
+pub fn day24(rng: &mut impl Rng, size: usize) -> String {
+    let pairs = size.clamp(1, 9);
+    // A random balanced sequence of pushes and pops
+    let mut pushes = Vec::new();
+    let mut pushed = 0;
+    let mut popped = 0;
+    let mut blocks = Vec::new();
+    while popped < pairs {
+        if pushed < pairs && (pushes.is_empty() || rng.gen_bool(0.5)) {
+            let offset = rng.gen_range(1, 17);
+            pushes.push(offset);
+            pushed += 1;
+            blocks.push((1, rng.gen_range(10, 17), offset));
+        } else {
+            let pushed_offset = pushes.pop().unwrap();
+            popped += 1;
+            let difference = rng.gen_range(-8, 9);
+            blocks.push((26, difference - pushed_offset, rng.gen_range(1, 17)));
+        }
+    }
+    blocks
+        .into_iter()
+        .map(|(divisor, check, offset)| {
+            format!(
+                "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\n\
+                mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\n\
+                mul y x\nadd z y",
+                divisor, check, offset
+            )
+        })
+        .join("\n")
+}
+
+/// A map of `size` by `size` sea cucumbers. The top row and the left column
+/// are walls of sea cucumbers that can never move, so that the others can not
+/// keep going around forever.
+pub fn day25(rng: &mut impl Rng, size: usize) -> String {
+    let size = size.max(2);
+    (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| match (x, y) {
+                    (_, 0) => '>',
+                    (0, _) => 'v',
+                    _ => *['>', 'v', '.'].choose(rng).unwrap(),
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+#[test]
+fn test_solvable() {
+    for day in 1..=25 {
+        for (seed, size) in [1, 2, 5, 10].iter().enumerate() {
+            let input = generate(day, seed as u64, *size).unwrap();
+            for part in 1..=2 {
+                if let Some(solver) = crate::solver::find(day, part, None) {
+                    if let Err(e) = solver.solve(&input) {
+                        panic!(
+                            "day {} part {} fails on {:?} (seed {}): {}",
+                            day, part, input, seed, e
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_deterministic() {
+    for day in 1..=25 {
+        assert_eq!(generate(day, 7, 5), generate(day, 7, 5));
+    }
+    assert_eq!(generate(26, 0, 5), None);
+    assert_ne!(generate(9, 0, 5), generate(9, 1, 5));
+}
+
+#[test]
+fn test_sizes() {
+    let lines = |day, size| generate(day, 0, size).unwrap().lines().count();
+    assert_eq!(lines(1, 50), 50);
+    assert_eq!(lines(3, 50), 50);
+    assert_eq!(lines(9, 50), 50);
+    assert_eq!(lines(4, 3), 1 + 3 * 6);
+    assert_eq!(lines(13, 20), 20 + 1 + 12);
+}
@@ -0,0 +1,377 @@
+use std::{fmt, slice};
+
+use thiserror::Error;
+
+use crate::position::Position;
+
+/// The dimensions of a grid. They are either fixed at compile time, which
+/// lets the compiler specialise for the size of the puzzle input, or only
+/// known at runtime.
+pub trait Size: Copy {
+    fn width(self) -> usize;
+    fn height(self) -> usize;
+
+    /// The index of `(x, y)` in the bytes of a grid of this size, in which
+    /// every line but the last is followed by a newline.
+    fn index(self, x: usize, y: usize) -> usize {
+        y * (self.width() + 1) + x
+    }
+
+    /// The number of bytes in a grid of this size, including the newlines.
+    fn byte_len(self) -> usize {
+        (self.height() * (self.width() + 1)).saturating_sub(1)
+    }
+
+    /// The neighbours of `(x, y)` to the left, right, top and bottom that lie
+    /// within the grid.
+    fn neighbours_4(self, x: usize, y: usize) -> Neighbours {
+        Neighbours::new(self, x, y, &ORTHOGONAL)
+    }
+
+    /// Like `neighbours_4`, but including the diagonal neighbours.
+    fn neighbours_8(self, x: usize, y: usize) -> Neighbours {
+        Neighbours::new(self, x, y, &ALL_DIRECTIONS)
+    }
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// An iterator over the neighbours of a point that lie within the grid.
+#[derive(Clone, Debug)]
+pub struct Neighbours {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    directions: slice::Iter<'static, (isize, isize)>,
+}
+
+impl Neighbours {
+    fn new<S: Size>(size: S, x: usize, y: usize, directions: &'static [(isize, isize)]) -> Self {
+        Neighbours {
+            x,
+            y,
+            width: size.width(),
+            height: size.height(),
+            directions: directions.iter(),
+        }
+    }
+}
+
+impl Iterator for Neighbours {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.directions.find_map(|&(dx, dy)| {
+            let x = self.x.checked_add_signed(dx)?;
+            let y = self.y.checked_add_signed(dy)?;
+            (x < self.width && y < self.height).then_some((x, y))
+        })
+    }
+}
+
+/// A grid size that is known at compile time.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Fixed<const WIDTH: usize, const HEIGHT: usize>;
+
+impl<const WIDTH: usize, const HEIGHT: usize> Size for Fixed<WIDTH, HEIGHT> {
+    fn width(self) -> usize {
+        WIDTH
+    }
+
+    fn height(self) -> usize {
+        HEIGHT
+    }
+}
+
+/// A grid size that is only known at runtime.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Dynamic {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Dynamic {
+    /// The size of a grid of lines separated by `\n`, as suggested by the
+    /// length of the first line and the number of lines. The input is not
+    /// checked for lines of other lengths.
+    pub fn of(input: &[u8]) -> Self {
+        if input.is_empty() {
+            return Dynamic {
+                width: 0,
+                height: 0,
+            };
+        }
+        Dynamic {
+            width: memchr::memchr(b'\n', input).unwrap_or(input.len()),
+            height: memchr::memchr_iter(b'\n', input).count() + 1,
+        }
+    }
+
+    /// Whether this is the same size as `S`.
+    pub fn is<S: Size + Default>(self) -> bool {
+        let fixed = S::default();
+        self.width == fixed.width() && self.height == fixed.height()
+    }
+}
+
+impl Size for Dynamic {
+    fn width(self) -> usize {
+        self.width
+    }
+
+    fn height(self) -> usize {
+        self.height
+    }
+}
+
+/// A size repeated `TIMES` times in both directions.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Repeated<S, const TIMES: usize>(pub S);
+
+impl<S: Size, const TIMES: usize> Size for Repeated<S, TIMES> {
+    fn width(self) -> usize {
+        self.0.width() * TIMES
+    }
+
+    fn height(self) -> usize {
+        self.0.height() * TIMES
+    }
+}
+
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum DigitMapError {
+    #[error("{0}: expected a digit")]
+    ExpectedDigit(Position),
+    #[error("{0}: expected a newline")]
+    ExpectedNewline(Position),
+    #[error("{0}: expected the end of the grid")]
+    ExpectedEnd(Position),
+}
+
+/// A grid of bytes borrowed from the input, in which every line but the last
+/// is followed by a newline.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Grid<'a, S: Size> {
+    bytes: &'a [u8],
+    size: S,
+}
+
+impl<'a, S: Size> Grid<'a, S> {
+    /// A grid of the given size. The lines in `bytes` should be separated by
+    /// a single newline.
+    pub fn new(bytes: &'a [u8], size: S) -> Self {
+        debug_assert_eq!(bytes.len(), size.byte_len());
+        Grid { bytes, size }
+    }
+
+    /// Parse a grid of the given size consisting of digits. The input should
+    /// have `\n` line endings.
+    pub fn parse_digits(input: &'a str, size: S) -> Result<Self, DigitMapError> {
+        let (width, height) = (size.width(), size.height());
+        let mut lines = 0;
+        for (y, line) in input.as_bytes().chunks(width + 1).enumerate() {
+            lines = y + 1;
+            let error_at = |x| Position {
+                line: y + 1,
+                column: x + 1,
+            };
+            if y == height {
+                return Err(DigitMapError::ExpectedEnd(error_at(0)));
+            }
+            if let Some(x) = line.iter().take(width).position(|b| !b.is_ascii_digit()) {
+                return Err(DigitMapError::ExpectedDigit(error_at(x)));
+            }
+            match line.get(width) {
+                None if line.len() < width => {
+                    return Err(DigitMapError::ExpectedDigit(error_at(line.len())))
+                }
+                None if y + 1 < height => {
+                    return Err(DigitMapError::ExpectedNewline(error_at(width)))
+                }
+                Some(b'\n') if y + 1 == height => {
+                    return Err(DigitMapError::ExpectedEnd(Position {
+                        line: y + 2,
+                        column: 1,
+                    }))
+                }
+                None | Some(b'\n') => {}
+                Some(_) => return Err(DigitMapError::ExpectedNewline(error_at(width))),
+            }
+        }
+        // The input may also end before the last line
+        if lines < height || input.is_empty() {
+            return Err(DigitMapError::ExpectedDigit(Position {
+                line: lines + 1,
+                column: 1,
+            }));
+        }
+        Ok(Grid::new(input.as_bytes(), size))
+    }
+
+    pub fn size(&self) -> S {
+        self.size
+    }
+
+    /// The bytes of the grid, including the newlines.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.bytes[self.size.index(x, y)]
+    }
+
+    /// The grid repeated `TIMES` times in both directions.
+    pub fn tiled<const TIMES: usize>(self) -> Tiled<'a, S, TIMES> {
+        Tiled { grid: self }
+    }
+}
+
+impl<S: Size> fmt::Display for Grid<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.bytes {
+            write!(f, "{}", char::from(*b))?;
+        }
+        Ok(())
+    }
+}
+
+/// A grid that is repeated `TIMES` times in both directions.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Tiled<'a, S: Size, const TIMES: usize> {
+    grid: Grid<'a, S>,
+}
+
+impl<S: Size, const TIMES: usize> Tiled<'_, S, TIMES> {
+    pub fn size(&self) -> Repeated<S, TIMES> {
+        Repeated(self.grid.size)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        let size = self.grid.size;
+        self.grid.get(x % size.width(), y % size.height())
+    }
+
+    /// The horizontal and vertical index of the tile containing `(x, y)`.
+    pub fn tile(&self, x: usize, y: usize) -> (usize, usize) {
+        let size = self.grid.size;
+        (x / size.width(), y / size.height())
+    }
+}
+
+#[test]
+fn test_dynamic_of() {
+    assert_eq!(
+        Dynamic::of(b"123\n456"),
+        Dynamic {
+            width: 3,
+            height: 2
+        }
+    );
+    assert_eq!(
+        Dynamic::of(b"12345"),
+        Dynamic {
+            width: 5,
+            height: 1
+        }
+    );
+    assert_eq!(
+        Dynamic::of(b""),
+        Dynamic {
+            width: 0,
+            height: 0
+        }
+    );
+    assert!(Dynamic::of(b"12\n34").is::<Fixed<2, 2>>());
+    assert!(!Dynamic::of(b"12\n34").is::<Fixed<2, 3>>());
+}
+
+#[test]
+fn test_neighbours() {
+    let size = Fixed::<3, 2>;
+    assert_eq!(
+        size.neighbours_4(0, 0).collect::<Vec<_>>(),
+        vec![(1, 0), (0, 1)]
+    );
+    assert_eq!(
+        size.neighbours_4(1, 1).collect::<Vec<_>>(),
+        vec![(0, 1), (2, 1), (1, 0)]
+    );
+    assert_eq!(
+        size.neighbours_8(2, 0).collect::<Vec<_>>(),
+        vec![(1, 0), (1, 1), (2, 1)]
+    );
+    assert_eq!(size.neighbours_8(1, 0).count(), 5);
+}
+
+#[test]
+fn test_parse_digits() {
+    let grid = Grid::parse_digits("123\n456", Fixed::<3, 2>).unwrap();
+    assert_eq!(grid.get(2, 0), b'3');
+    assert_eq!(grid.get(0, 1), b'4');
+    assert_eq!(grid.to_string(), "123\n456");
+    assert_eq!(
+        Grid::parse_digits("123\n4x6", Fixed::<3, 2>),
+        Err(DigitMapError::ExpectedDigit(Position {
+            line: 2,
+            column: 2
+        }))
+    );
+    assert_eq!(
+        Grid::parse_digits("1234\n456", Fixed::<3, 2>),
+        Err(DigitMapError::ExpectedNewline(Position {
+            line: 1,
+            column: 4
+        }))
+    );
+    assert_eq!(
+        Grid::parse_digits("123\n456\n789", Fixed::<3, 2>),
+        Err(DigitMapError::ExpectedEnd(Position { line: 3, column: 1 }))
+    );
+    assert_eq!(
+        Grid::parse_digits("123\n", Fixed::<3, 2>),
+        Err(DigitMapError::ExpectedDigit(Position {
+            line: 2,
+            column: 1
+        }))
+    );
+    assert_eq!(
+        Grid::parse_digits("123", Fixed::<3, 3>),
+        Err(DigitMapError::ExpectedNewline(Position {
+            line: 1,
+            column: 4
+        }))
+    );
+    assert_eq!(
+        Grid::parse_digits("123\n456\n", Fixed::<3, 2>),
+        Err(DigitMapError::ExpectedEnd(Position { line: 3, column: 1 }))
+    );
+    assert_eq!(
+        Grid::parse_digits("", Dynamic::of(b"")),
+        Err(DigitMapError::ExpectedDigit(Position {
+            line: 1,
+            column: 1
+        }))
+    );
+}
+
+#[test]
+fn test_tiled() {
+    let grid = Grid::parse_digits("12\n34", Fixed::<2, 2>).unwrap();
+    let tiled = grid.tiled::<3>();
+    assert_eq!((tiled.size().width(), tiled.size().height()), (6, 6));
+    assert_eq!(tiled.get(5, 4), b'2');
+    assert_eq!(tiled.tile(5, 4), (2, 2));
+    assert_eq!(tiled.tile(1, 1), (0, 0));
+}
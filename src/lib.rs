@@ -0,0 +1,41 @@
+//! Solutions to the puzzles of Advent of Code 2021. Every day has a module
+//! with a `part_1` and a `part_2` function that take the puzzle input, next to
+//! the parser and the types the solution is built on. The solutions are also
+//! listed in [`solver::SOLVERS`], to run them by day and part.
+
+#[cfg(test)]
+mod answers;
+pub mod cli;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+#[cfg(test)]
+mod differential;
+pub mod generate;
+pub mod grid;
+mod normalize;
+pub mod position;
+#[macro_use]
+pub mod solver;
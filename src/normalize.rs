@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+
+/// Bring the puzzle input in the form the parsers expect: `\r\n` line endings
+/// are replaced by `\n` and trailing newlines are removed. The input is only
+/// copied if it contains a carriage return, so that the common case stays
+/// free.
+///
+/// Only carriage returns are removed, so positions in the normalized input
+/// point at the same line and column as in the original input.
+pub fn line_endings(input: &str) -> Cow<'_, str> {
+    let input = input.trim_end_matches(['\n', '\r']);
+    if memchr::memchr(b'\r', input.as_bytes()).is_some() {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+#[test]
+fn test_line_endings() {
+    assert!(matches!(line_endings("a\nb"), Cow::Borrowed("a\nb")));
+    assert!(matches!(line_endings("a\nb\n\n"), Cow::Borrowed("a\nb")));
+    assert_eq!(line_endings("a\r\nb\r\n"), "a\nb");
+    assert_eq!(line_endings("a\r\n\r\nb"), "a\n\nb");
+    assert_eq!(line_endings(""), "");
+}
+
+/// Defines a test per day that runs the parts on its puzzle input with a
+/// trailing newline and with `\r\n` line endings, and checks that the answers
+/// are the same as for the plain input. The input is `input/2021/<day>.txt`,
+/// unless another file is given with `in`.
+#[cfg(test)]
+macro_rules! test_line_ending_variants {
+    (@input $day:ident) => {
+        include_str!(concat!("../input/2021/", stringify!($day), ".txt"))
+    };
+    (@input $day:ident $file:literal) => {
+        include_str!(concat!("../input/2021/", $file))
+    };
+    ($($day:ident $(in $file:literal)? => $($part:ident),+;)+) => {
+        $(
+            #[test]
+            fn $day() {
+                let input = test_line_ending_variants!(@input $day $($file)?)
+                    .trim_end_matches('\n');
+                let crlf = input.replace('\n', "\r\n");
+                let variants = [format!("{}\n", input), crlf.clone(), format!("{}\r\n", crlf)];
+                for variant in variants.iter() {
+                    $(
+                        assert_eq!(
+                            crate::$day::$part(variant),
+                            crate::$day::$part(input),
+                            "{}::{} on {:?}",
+                            stringify!($day),
+                            stringify!($part),
+                            &variant[variant.len().saturating_sub(4)..]
+                        );
+                    )+
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(test)]
+mod line_ending_variants {
+    test_line_ending_variants! {
+        day1 => part_1, part_2;
+        day2 => part_1, part_2;
+        day3 => part_1, part_2, part_1_code_golf;
+        day4 => part_1, part_2;
+        day5 => part_1, part_2;
+        day6 => part_1, part_2;
+        day7 => part_1, part_2_incrementing_mu, part_2_mean;
+        day8 => part_1, part_2, part_2_frequency_table;
+        day9 => part_1, part_2;
+        day10 => part_1, part_2;
+        day11 => part_1, part_2;
+        day12 => part_1, part_2;
+        day13 => part_1, part_2;
+        day14 => part_1, part_2;
+        day15 => part_1, part_2;
+        day16 => part_1, part_2;
+        day17 => part_1, part_2;
+        day18 in "day18-example.txt" => part_1, part_2;
+        day19 in "day19-example.txt" => part_1, part_2;
+        day20 in "day20-example.txt" => part_1, part_2;
+        day21 => part_1, part_2;
+        day22 in "day22-example.txt" => part_1, part_2;
+        day23 in "day23-example.txt" => part_1, part_2;
+        day24 in "day24-example.txt" => part_1, part_2;
+        day25 in "day25-example.txt" => part_1;
+    }
+}
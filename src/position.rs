@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// A position in the puzzle input, used for reporting parse errors. Both the
+/// line and the column start counting at 1.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the byte at `offset` in `input`.
+    pub fn of(input: &[u8], offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = memchr::memrchr(b'\n', before).map_or(0, |pos| pos + 1);
+        Position {
+            line: memchr::memchr_iter(b'\n', before).count() + 1,
+            column: offset - line_start + 1,
+        }
+    }
+
+    /// The position of the start of `remainder`, which should be a suffix of
+    /// `input`.
+    pub fn of_remainder(input: &[u8], remainder: &[u8]) -> Self {
+        debug_assert!(input.ends_with(remainder));
+        Position::of(input, input.len() - remainder.len())
+    }
+
+    /// The position at which a `nom` parser failed.
+    pub fn of_nom_error<I: AsRef<[u8]>>(
+        input: &[u8],
+        error: &nom::Err<nom::error::Error<I>>,
+    ) -> Self {
+        match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                Position::of_remainder(input, e.input.as_ref())
+            }
+            nom::Err::Incomplete(_) => Position::of(input, input.len()),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[test]
+fn test_position() {
+    let input = b"abc\nde\n\nf";
+    assert_eq!(Position::of(input, 0), Position { line: 1, column: 1 });
+    assert_eq!(Position::of(input, 2), Position { line: 1, column: 3 });
+    assert_eq!(Position::of(input, 3), Position { line: 1, column: 4 });
+    assert_eq!(Position::of(input, 4), Position { line: 2, column: 1 });
+    assert_eq!(Position::of(input, 8), Position { line: 4, column: 1 });
+    assert_eq!(Position::of(input, 9), Position { line: 4, column: 2 });
+    assert_eq!(
+        Position::of_remainder(input, b"e\n\nf"),
+        Position { line: 2, column: 2 }
+    );
+    assert_eq!(
+        Position { line: 4, column: 2 }.to_string(),
+        "line 4, column 2"
+    );
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+
+/// Calls the macro `$callback` with the table of all solutions, in the syntax
+/// of `aoc_main::main!`. Every function in the table should be called
+/// `part_1` or `part_2`, optionally followed by `_` and the name of the
+/// variant.
+#[macro_export]
+macro_rules! with_solutions {
+    ($($callback:ident)::+) => {
+        $($callback)::+! {
+            year 2021;
+            day1 => part_1, part_2, part_1_ring_buffer, part_2_ring_buffer;
+            day2 => part_1, part_2;
+            day3 => part_1, part_2, part_1_code_golf;
+            day4 => part_1, part_2;
+            day5 => part_1, part_2;
+            day6 => part_1, part_2;
+            day7 => part_1, part_2_incrementing_mu, part_2_mean;
+            day8 => part_1, part_2, part_2_frequency_table;
+            day9 => part_1, part_2;
+            day10 => part_1, part_2;
+            day11 => part_1, part_2;
+            day12 => part_1, part_2;
+            day13 => part_1, part_2;
+            day14 => part_1, part_2;
+            day15 => part_1, part_2;
+            day16 => part_1, part_2;
+            day17 => part_1, part_2;
+            day18 => part_1, part_2;
+            day19 => part_1, part_2;
+            day20 => part_1, part_2;
+            day21 => part_1, part_2;
+            day22 => part_1, part_2;
+            day23 => part_1, part_2;
+            day24 => part_1, part_2;
+            day25 => part_1;
+        }
+    };
+}
+
+/// The answer to a puzzle.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// The value returned by a solution: an answer, or a result containing one.
+pub trait IntoAnswer {
+    fn into_answer(self) -> anyhow::Result<Answer>;
+}
+
+macro_rules! impl_into_answer_for_numbers {
+    ($($t:ty),+) => {
+        $(
+            impl IntoAnswer for $t {
+                fn into_answer(self) -> anyhow::Result<Answer> {
+                    Ok(Answer::Number(self as i128))
+                }
+            }
+        )+
+    };
+}
+
+impl_into_answer_for_numbers!(i32, i64, u32, u64, usize);
+
+impl IntoAnswer for u128 {
+    fn into_answer(self) -> anyhow::Result<Answer> {
+        // Answers that do not fit in a number are still shown
+        Ok(i128::try_from(self).map_or_else(|_| Answer::Text(self.to_string()), Answer::Number))
+    }
+}
+
+impl IntoAnswer for String {
+    fn into_answer(self) -> anyhow::Result<Answer> {
+        Ok(Answer::Text(self))
+    }
+}
+
+impl<T: IntoAnswer, E: std::error::Error + Send + Sync + 'static> IntoAnswer for Result<T, E> {
+    fn into_answer(self) -> anyhow::Result<Answer> {
+        self?.into_answer()
+    }
+}
+
+/// A solution to one part of a puzzle.
+pub trait Solver: Sync {
+    fn day(&self) -> u8;
+    fn part(&self) -> u8;
+    /// The name of the variant, to tell apart different solutions to the same
+    /// part. It is `"default"` for the functions called just `part_1` or
+    /// `part_2`.
+    fn variant(&self) -> &'static str;
+    fn solve(&self, input: &str) -> anyhow::Result<Answer>;
+}
+
+/// A solver that calls one of the `part_*` functions of a day.
+struct Solution {
+    day: u8,
+    part: u8,
+    function: &'static str,
+    solve: fn(&str) -> anyhow::Result<Answer>,
+}
+
+impl Solver for Solution {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn part(&self) -> u8 {
+        self.part
+    }
+
+    fn variant(&self) -> &'static str {
+        self.function.get("part_1_".len()..).unwrap_or("default")
+    }
+
+    fn solve(&self, input: &str) -> anyhow::Result<Answer> {
+        (self.solve)(input)
+    }
+}
+
+/// Parse the number in `day1` to `day25`.
+const fn day_number(module: &str) -> u8 {
+    let bytes = module.as_bytes();
+    let mut number = 0;
+    let mut i = "day".len();
+    while i < bytes.len() {
+        number = number * 10 + (bytes[i] - b'0');
+        i += 1;
+    }
+    number
+}
+
+/// The part of a function called `part_1` or `part_2`, optionally followed
+/// by the name of the variant.
+const fn part_number(function: &str) -> u8 {
+    function.as_bytes()["part_".len()] - b'0'
+}
+
+macro_rules! registry {
+    (year $year:expr; $($day:ident => $($function:ident),+;)+) => {
+        /// Every solution, ordered by day and then by the order in which they
+        /// are listed in `with_solutions`.
+        pub static SOLVERS: &[&dyn Solver] = &[$($(&Solution {
+            day: day_number(stringify!($day)),
+            part: part_number(stringify!($function)),
+            function: stringify!($function),
+            solve: |input| crate::$day::$function(input).into_answer(),
+        },)+)+];
+    };
+}
+
+with_solutions!(registry);
+
+/// The solver for the given day and part. If no variant is given, the first
+/// solution to the part is returned.
+pub fn find(day: u8, part: u8, variant: Option<&str>) -> Option<&'static dyn Solver> {
+    SOLVERS
+        .iter()
+        .copied()
+        .filter(|s| s.day() == day && s.part() == part)
+        .find(|s| variant.is_none_or(|v| s.variant() == v))
+}
+
+#[test]
+fn test_registry() {
+    assert_eq!(SOLVERS.len(), 54);
+    let mean = find(7, 2, Some("mean")).unwrap();
+    assert_eq!((mean.day(), mean.part()), (7, 2));
+    assert_eq!(find(7, 2, None).unwrap().variant(), "incrementing_mu");
+    assert_eq!(find(3, 1, None).unwrap().variant(), "default");
+    assert_eq!(find(3, 1, Some("code_golf")).unwrap().part(), 1);
+    assert!(find(25, 2, None).is_none());
+    assert!(find(8, 2, Some("golf")).is_none());
+}
+
+#[test]
+fn test_solve() {
+    let input = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+    assert_eq!(
+        find(1, 1, None).unwrap().solve(input).unwrap(),
+        Answer::Number(7)
+    );
+    assert!(find(1, 1, None).unwrap().solve("199\nx").is_err());
+}